@@ -76,6 +76,9 @@ impl Token {
             }
         }
         editor.append(&mut job, self);
+        if let Some(error_line) = editor.error_line {
+            mark_line(&mut job, text, error_line);
+        }
         job
     }
 
@@ -249,6 +252,48 @@ pub fn highlight(ctx: &egui::Context, cache: &CodeEditor, text: &str) -> LayoutJ
     ctx.memory_mut(|mem| mem.caches.cache::<HighlightCache>().get((cache, text)))
 }
 
+// Splits the sections crossing the given 1-based line so only that line gets the error background.
+#[cfg(feature = "egui")]
+fn mark_line(job: &mut LayoutJob, text: &str, line: usize) {
+    if line == 0 {
+        return;
+    }
+    let start: usize = text
+        .split_inclusive('\n')
+        .take(line - 1)
+        .map(str::len)
+        .sum();
+    let end = text[start..]
+        .find('\n')
+        .map(|i| start + i + 1)
+        .unwrap_or(text.len());
+    let background = egui::Color32::from_rgba_unmultiplied(0xe0, 0x4a, 0x3f, 0x40);
+    let mut sections = Vec::with_capacity(job.sections.len() + 2);
+    for section in job.sections.drain(..) {
+        let range = section.byte_range.clone();
+        if range.end <= start || range.start >= end {
+            sections.push(section);
+            continue;
+        }
+        let inner = range.start.max(start)..range.end.min(end);
+        if range.start < inner.start {
+            let mut before = section.clone();
+            before.byte_range = range.start..inner.start;
+            sections.push(before);
+        }
+        let mut marked = section.clone();
+        marked.byte_range = inner.clone();
+        marked.format.background = background;
+        sections.push(marked);
+        if inner.end < range.end {
+            let mut after = section;
+            after.byte_range = inner.end..range.end;
+            sections.push(after);
+        }
+    }
+    job.sections = sections;
+}
+
 #[cfg(feature = "egui")]
 impl CodeEditor {
     fn append(&self, job: &mut LayoutJob, token: &Token) {
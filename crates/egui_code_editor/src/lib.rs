@@ -21,6 +21,9 @@ mod syntax;
 mod tests;
 mod themes;
 
+#[cfg(feature = "egui")]
+const ERROR_LINE_COLOR: egui::Color32 = egui::Color32::from_rgb(0xe0, 0x4a, 0x3f);
+
 #[derive(Clone, Debug, PartialEq)]
 /// CodeEditor struct which stores settings for highlighting.
 pub struct CodeEditor {
@@ -33,6 +36,7 @@ pub struct CodeEditor {
     vscroll: bool,
     stick_to_bottom: bool,
     shrink: bool,
+    error_line: Option<usize>,
     _prompt: Prompt,
     _popup_id:Id,
 }
@@ -58,6 +62,7 @@ impl Hash for CodeEditor {
         #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
         (self.fontsize as u32).hash(state);
         self.syntax.hash(state);
+        self.error_line.hash(state);
     }
 }
 
@@ -73,6 +78,7 @@ impl Default for CodeEditor {
             vscroll: true,
             stick_to_bottom: false,
             shrink: false,
+            error_line: None,
             _prompt: Prompt::default(),
             _popup_id: Id::new("code_editor_prompt"),
         }
@@ -133,6 +139,13 @@ impl CodeEditor {
         CodeEditor { syntax, ..self }
     }
 
+    /// Mark a line (1-based) as failing, its number and background are drawn in red.
+    ///
+    /// **Default: None**
+    pub fn with_error_line(self, error_line: Option<usize>) -> Self {
+        CodeEditor { error_line, ..self }
+    }
+
     pub fn with_prompt(self, prompt: Prompt) -> Self {
         CodeEditor { _prompt: prompt, ..self }
     }
@@ -197,13 +210,19 @@ impl CodeEditor {
         let width = max_indent as f32 * self.fontsize * 0.5;
 
         let mut layouter = |ui: &egui::Ui, string: &str, _wrap_width: f32| {
-            let layout_job = egui::text::LayoutJob::single_section(
-                string.to_string(),
-                egui::TextFormat::simple(
-                    egui::FontId::monospace(self.fontsize),
-                    self.theme.type_color(TokenType::Comment(true)),
-                ),
-            );
+            let mut layout_job = egui::text::LayoutJob::default();
+            for (index, line) in string.split_inclusive('\n').enumerate() {
+                let color = if self.error_line == Some(index + 1) {
+                    ERROR_LINE_COLOR
+                } else {
+                    self.theme.type_color(TokenType::Comment(true))
+                };
+                layout_job.append(
+                    line,
+                    0.0,
+                    egui::TextFormat::simple(egui::FontId::monospace(self.fontsize), color),
+                );
+            }
             ui.fonts(|f| f.layout_job(layout_job))
         };
 
@@ -63,3 +63,19 @@ fn simple_rust() {
     println!("{str}");
     assert_eq!(input, output);
 }
+
+#[test]
+fn error_line_background() {
+    let text = "let a = 1;\nthrow err;\nlet b = 2;";
+    let editor = CodeEditor::default()
+        .with_syntax(Syntax::new("js"))
+        .with_error_line(Some(2));
+    let job = Token::default().highlight(&editor, text);
+    let marked: String = job
+        .sections
+        .iter()
+        .filter(|s| s.format.background != egui::Color32::TRANSPARENT)
+        .map(|s| &text[s.byte_range.clone()])
+        .collect();
+    assert_eq!(marked, "throw err;\n");
+}
@@ -109,6 +109,7 @@ impl OpenApi {
                     value: host_value,
                     desc: "".to_string(),
                     value_type: Default::default(),
+                    file_encoding: Default::default(),
                 }],
             },
             openapi: Some(self.openapi_help.openapi.clone()),
@@ -129,6 +130,7 @@ impl OpenApi {
                 pre_request_script: "".to_string(),
                 test_script: "".to_string(),
                 testcases: Default::default(),
+                envs: Default::default(),
             })),
         };
 
@@ -188,6 +190,7 @@ impl OpenApi {
                 pre_request_script: "".to_string(),
                 test_script: "".to_string(),
                 testcases: Default::default(),
+                envs: Default::default(),
             })
             .collect();
         let mut result = BTreeMap::default();
@@ -256,6 +259,8 @@ impl OpenApi {
                             .collect(),
                         body: self.gen_http_body(op.operation.request_body.clone()),
                         auth: Auth::default(),
+                        protocol: Default::default(),
+                        disable_decompression: false,
                     },
                     ..Default::default()
                 })
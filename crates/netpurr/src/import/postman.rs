@@ -47,9 +47,10 @@ impl Postman {
                     is_root: true,
                     requests: PostmanItemGroup::gen_requests(self.item.clone()),
                     folders: PostmanItemGroup::gen_folders(self.item.clone()),
-                    pre_request_script: "".to_string(),
-                    test_script: "".to_string(),
+                    pre_request_script: PostmanEvent::script(&self.event, "prerequest"),
+                    test_script: PostmanEvent::script(&self.event, "test"),
                     testcases: Default::default(),
+                    envs: Default::default(),
                 })),
             };
             Ok(collection)
@@ -132,7 +133,11 @@ impl PostmanItemGroup {
                             .collect(),
                         body: p.request.body.to(),
                         auth: p.auth.to(),
+                        protocol: Default::default(),
+                        disable_decompression: false,
                     },
+                    pre_request_script: PostmanEvent::script(&p.event, "prerequest"),
+                    test_script: PostmanEvent::script(&p.event, "test"),
                     ..Default::default()
                 })
             })
@@ -159,9 +164,10 @@ impl PostmanItemGroup {
                 is_root: false,
                 requests: PostmanItemGroup::gen_requests(p.item.clone()),
                 folders: PostmanItemGroup::gen_folders(p.item.clone()),
-                pre_request_script: "".to_string(),
-                test_script: "".to_string(),
+                pre_request_script: PostmanEvent::script(&p.event, "prerequest"),
+                test_script: PostmanEvent::script(&p.event, "test"),
                 testcases: Default::default(),
+                envs: Default::default(),
             })
             .collect();
         let mut result = BTreeMap::default();
@@ -191,6 +197,7 @@ impl PostmanVariable {
             value: self.value.clone(),
             desc: self.description.clone(),
             value_type: EnvironmentValueType::String,
+            file_encoding: Default::default(),
         }
     }
 }
@@ -405,4 +412,81 @@ pub struct PostmanQuery {
 pub struct PostmanEvent {
     listen: String,
     disabled: bool,
+    script: PostmanScript,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct PostmanScript {
+    exec: PostmanScriptExec,
+}
+
+/// `exec` is a list of lines in exported collections, some tools write a single string.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum PostmanScriptExec {
+    Lines(Vec<String>),
+    Text(String),
+}
+
+impl Default for PostmanScriptExec {
+    fn default() -> Self {
+        PostmanScriptExec::Lines(vec![])
+    }
+}
+
+impl PostmanEvent {
+    /// Joins the enabled `listen` scripts, they run on the `pm.*` shim of runtime.js.
+    pub fn script(events: &Vec<PostmanEvent>, listen: &str) -> String {
+        events
+            .iter()
+            .filter(|e| !e.disabled && e.listen == listen)
+            .map(|e| match &e.script.exec {
+                PostmanScriptExec::Lines(lines) => lines.join("\n"),
+                PostmanScriptExec::Text(text) => text.clone(),
+            })
+            .filter(|script| !script.trim().is_empty())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(json: &str) -> Vec<PostmanEvent> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn script_joins_exec_lines() {
+        let events = events(
+            r#"[
+                {"listen": "prerequest", "script": {"exec": ["let a = 1;", "pm.environment.set(\"a\", a);"]}},
+                {"listen": "test", "script": {"exec": ["pm.test(\"ok\", () => {});"]}}
+            ]"#,
+        );
+        assert_eq!(
+            PostmanEvent::script(&events, "prerequest"),
+            "let a = 1;\npm.environment.set(\"a\", a);"
+        );
+        assert_eq!(
+            PostmanEvent::script(&events, "test"),
+            "pm.test(\"ok\", () => {});"
+        );
+    }
+
+    #[test]
+    fn script_accepts_exec_text_and_skips_disabled_events() {
+        let events = events(
+            r#"[
+                {"listen": "test", "script": {"exec": "let a = 1;\nlet b = 2;"}},
+                {"listen": "test", "disabled": true, "script": {"exec": "let c = 3;"}},
+                {"listen": "test", "script": {"exec": "  "}}
+            ]"#,
+        );
+        assert_eq!(PostmanEvent::script(&events, "test"), "let a = 1;\nlet b = 2;");
+        assert_eq!(PostmanEvent::script(&events, "prerequest"), "");
+    }
 }
@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::str::FromStr;
 use futures_util::AsyncWriteExt;
 
@@ -24,7 +24,7 @@ impl Git {
         if repo.is_err() {
             error!("init git repo failed, path: {:?}", repo_path);
         }
-        let gitignore_content = ".DS_Store\nrequests/";
+        let gitignore_content = ".DS_Store\nrequests/\nresponses/\nenvironment/secrets/";
         let gitignore_path = repo_path.join("./.gitignore");
         let mut file = File::create(gitignore_path);
         if file.is_ok() {
@@ -57,9 +57,15 @@ impl Git {
         }
     }
 
-    pub fn git_sync_promise(&self, repo_path: PathBuf) -> Promise<rustygit::types::Result<()>> {
+    /// `files` are the workspace files referenced by file variables, they are added even when
+    /// they are ignored.
+    pub fn git_sync_promise(
+        &self,
+        repo_path: PathBuf,
+        files: Vec<String>,
+    ) -> Promise<rustygit::types::Result<()>> {
         Promise::spawn_thread("git_thread", move || -> rustygit::types::Result<()> {
-            let repo = Repository::new(repo_path);
+            let repo = Repository::new(repo_path.clone());
             if let Ok(head) = repo.cmd_out(["branch", "--show-current"]) {
                 if let Some(branch_name) = head.get(0) {
                     repo.cmd([
@@ -68,6 +74,16 @@ impl Git {
                     ])?;
                     repo.cmd(["add", "."])?;
                     repo.cmd(["rm", "-rf", "--ignore-unmatch","--cached", "requests/*"])?;
+                    repo.cmd(["rm", "-rf", "--ignore-unmatch","--cached", "responses/*"])?;
+                    repo.cmd(["rm", "-rf", "--ignore-unmatch","--cached", "environment/secrets/*"])?;
+                    for file in files.iter().filter(|f| Self::inside_repo(f)) {
+                        if !repo_path.join(file).is_file() {
+                            continue;
+                        }
+                        if let Err(e) = repo.cmd(["add", "-f", "--", file.as_str()]) {
+                            error!("git add file variable failed, path: {}, {:?}", file, e);
+                        }
+                    }
                     repo.commit_all("auto commit");
                     repo.cmd(["pull", "--rebase"])?;
                     repo.cmd(["push", "--set-upstream", "origin", &branch_name])
@@ -79,6 +95,11 @@ impl Git {
             }
         })
     }
+    fn inside_repo(file: &str) -> bool {
+        Path::new(file)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    }
     pub fn git_force_pull_promise(
         &self,
         repo_path: PathBuf,
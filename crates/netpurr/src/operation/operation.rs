@@ -119,6 +119,7 @@ impl Operation {
         &self,
         http_request: Request,
         envs: BTreeMap<String, EnvironmentItemValue>,
+        environment: Option<String>,
         pre_request_scripts: Vec<ScriptScope>,
         test_scripts: Vec<ScriptScope>,
     ) -> WebSocketSession {
@@ -131,6 +132,15 @@ impl Operation {
             pre_request_scripts,
             test_scripts,
             testcase: Default::default(),
+            websocket_scenario: None,
+            download: Default::default(),
+            cancel_token: Default::default(),
+            openapi: None,
+            operation_id: None,
+            lib_dir: None,
+            workspace_dir: None,
+            script_limits: Default::default(),
+            environment,
         })
     }
 
@@ -57,7 +57,10 @@ impl BottomPanel {
                             } else {
                                 if ui.button(egui_phosphor::regular::ARROW_CLOCKWISE).clicked() {
                                     self.sync_promise =
-                                        Some(operation.git().git_sync_promise(workspace.path.clone()));
+                                        Some(operation.git().git_sync_promise(
+                                            workspace.path.clone(),
+                                            workspace_data.get_env_files().into_iter().collect(),
+                                        ));
                                 }
                             }
                         }
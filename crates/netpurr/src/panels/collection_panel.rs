@@ -16,6 +16,7 @@ use crate::utils;
 use crate::windows::new_collection_windows::NewCollectionWindows;
 use crate::windows::save_crt_windows::SaveCRTWindows;
 use crate::windows::save_windows::SaveWindows;
+use crate::windows::variable_lint_windows::VariableLintWindows;
 
 #[derive(Default)]
 pub struct CollectionPanel {}
@@ -156,11 +157,14 @@ impl CollectionPanel {
                             headers: vec![],
                             body: Default::default(),
                             auth: Default::default(),
+                            protocol: Default::default(),
+                            disable_decompression: false,
                         },
                         .. Default::default()
                     }),
                     test_result: Default::default(),
                     modify_baseline: "".to_string(),
+                    script_changes: Default::default(),
                 };
                 workspace_data.add_crt(crt.clone());
                 operation.add_window(Box::new(SaveCRTWindows::default().with(
@@ -170,6 +174,12 @@ impl CollectionPanel {
                 workspace_data.set_crt_select_id(Some(crt.id.clone()));
                 ui.close_menu();
             }
+            if utils::select_label(ui, "Lint Variables").clicked() {
+                operation.add_window(Box::new(
+                    VariableLintWindows::default().with(collection.clone(), folder.clone()),
+                ));
+                ui.close_menu();
+            }
             if utils::select_label(ui, "Duplicate").clicked() {
                 let new_name = utils::build_copy_name(
                     folder_name.clone(),
@@ -12,6 +12,7 @@ pub mod request_body_xxx_form_panel;
 pub mod request_headers_panel;
 pub mod request_params_panel;
 pub mod request_pre_script_panel;
+pub mod request_variables_panel;
 pub mod response_body_panel;
 pub mod response_cookies_panel;
 pub mod response_headers_panel;
@@ -28,6 +29,7 @@ pub mod top_panel;
 pub mod websocket_content_panel;
 pub mod websocket_event_panel;
 pub mod websocket_panel;
+pub mod websocket_scenario_panel;
 
 pub const HORIZONTAL_GAP: f32 = 8.0;
 pub const VERTICAL_GAP: f32 = 8.0;
@@ -159,6 +159,12 @@ impl RequestBodyFormDataPanel {
                                 param.value = path.display().to_string();
                             }
                         }
+                        // a path or a `{{file variable}}`
+                        HighlightTemplateSinglelineBuilder::default()
+                            .envs(envs.clone())
+                            .all_space(false)
+                            .build("request_body_from_data_file".to_string(), &mut param.value)
+                            .ui(ui);
                     }
                 });
                 row.col(|ui| {
@@ -3,7 +3,7 @@ use serde_json::Value;
 use strum::IntoEnumIterator;
 use uuid::Uuid;
 
-use netpurr_core::data::http::{BodyRawType, BodyType};
+use netpurr_core::data::http::{BodyRawType, BodyType, ContentEncoding};
 use netpurr_core::data::workspace_data::WorkspaceData;
 
 use crate::operation::operation::Operation;
@@ -70,6 +70,29 @@ impl RequestBodyPanel {
                             });
                         }
                     });
+                egui::ComboBox::from_id_source("body_encoding")
+                    .selected_text(
+                        crt.record
+                            .must_get_rest()
+                            .request
+                            .body
+                            .body_encoding
+                            .clone()
+                            .to_string(),
+                    )
+                    .show_ui(ui, |ui| {
+                        ui.style_mut().wrap = Some(false);
+                        ui.set_min_width(60.0);
+                        for body_encoding in ContentEncoding::iter() {
+                            crt = workspace_data.must_get_mut_crt(crt_id.clone(), |crt| {
+                                ui.selectable_value(
+                                    &mut crt.record.must_get_mut_rest().request.body.body_encoding,
+                                    body_encoding.clone(),
+                                    body_encoding.to_string(),
+                                );
+                            });
+                        }
+                    });
                 if crt.record.must_get_rest().request.body.body_raw_type == BodyRawType::JSON {
                     if ui.button("Pretty").clicked() {
                         let json = crt.record.must_get_rest().request.body.body_str.clone();
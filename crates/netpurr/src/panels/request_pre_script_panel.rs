@@ -6,7 +6,9 @@ use prettify_js::prettyprint;
 use egui_code_editor::{CodeEditor, ColorTheme, Prompt};
 use netpurr_core::data::workspace_data::{TestItem, WorkspaceData};
 
+use crate::operation::operation::Operation;
 use crate::widgets::syntax::js_syntax;
+use crate::windows::script_library_windows::ScriptLibraryWindows;
 
 #[derive(Default)]
 pub struct RequestPreScriptPanel {}
@@ -15,6 +17,7 @@ impl RequestPreScriptPanel {
     pub fn set_and_render(
         &mut self,
         ui: &mut Ui,
+        operation: &Operation,
         workspace_data: &mut WorkspaceData,
         test_item: TestItem,
     ) {
@@ -79,6 +82,15 @@ impl RequestPreScriptPanel {
                                 if ui.link("Sleep").clicked() {
                                     script = script.clone().add("\nawait sleep(1000);");
                                 }
+                                for lib in workspace_data.get_script_libs() {
+                                    if ui.link(format!("Import {}", lib)).clicked() {
+                                        let stem = lib.trim_end_matches(".js").replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+                                        script = format!("import * as {} from \"lib:{}\";\n{}", stem, lib, script);
+                                    }
+                                }
+                                if ui.link("Manage script library").clicked() {
+                                    operation.add_window(Box::new(ScriptLibraryWindows::default()));
+                                }
                                 if ui.link("Fetch a http request").clicked() {
                                     script = script.clone().add(
                                         r#"let request = {
@@ -97,6 +109,7 @@ console.log(response)"#)
                         });
             });
         });
+        let error_line = workspace_data.script_error_line(&test_item, script.as_str());
         ui.push_id("pre_request_script", |ui| {
             egui::ScrollArea::vertical()
                 .min_scrolled_height(ui.available_height()-30.0)
@@ -109,6 +122,7 @@ console.log(response)"#)
                         .with_ui_fontsize(ui)
                         .with_syntax(js_syntax())
                         .with_prompt(Prompt::from_str(prompt_yaml))
+                        .with_error_line(error_line)
                         .with_numlines(true);
                     if ui.visuals().dark_mode {
                         code_editor = code_editor.with_theme(ColorTheme::GRUVBOX)
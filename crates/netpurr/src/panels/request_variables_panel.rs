@@ -0,0 +1,100 @@
+use egui::{Align, Button, Checkbox, Layout, TextEdit, Ui, Widget};
+use egui_extras::{Column, TableBuilder};
+
+use netpurr_core::data::environment::{EnvironmentConfig, EnvironmentItem, VARIABLE_PRECEDENCE};
+use netpurr_core::data::workspace_data::WorkspaceData;
+
+use crate::panels::VERTICAL_GAP;
+
+#[derive(Default)]
+pub struct RequestVariablesPanel {
+    new_item: EnvironmentItem,
+}
+
+impl RequestVariablesPanel {
+    pub fn set_and_render(&mut self, ui: &mut Ui, workspace_data: &mut WorkspaceData, crt_id: String) {
+        workspace_data.must_get_mut_crt(crt_id.clone(), |crt| {
+            ui.label("These variables are specific to this request.");
+            ui.small(VARIABLE_PRECEDENCE);
+            ui.add_space(VERTICAL_GAP);
+            self.render_table(ui, "request_variables_table", &mut crt.record.must_get_mut_rest().envs);
+        });
+    }
+
+    pub fn render_table(&mut self, ui: &mut Ui, id: &str, envs: &mut EnvironmentConfig) {
+        let mut delete_index = None;
+        ui.push_id(id, |ui| {
+            let table = TableBuilder::new(ui)
+                .resizable(false)
+                .cell_layout(Layout::left_to_right(Align::Center))
+                .column(Column::auto())
+                .column(Column::exact(20.0))
+                .column(Column::initial(200.0).range(40.0..=300.0))
+                .column(Column::remainder())
+                .max_scroll_height(400.0);
+            table
+                .header(20.0, |mut header| {
+                    header.col(|ui| {
+                        ui.strong("");
+                    });
+                    header.col(|ui| {
+                        ui.strong("");
+                    });
+                    header.col(|ui| {
+                        ui.strong("VARIABLE");
+                    });
+                    header.col(|ui| {
+                        ui.strong("VALUE");
+                    });
+                })
+                .body(|mut body| {
+                    for (index, item) in envs.items.iter_mut().enumerate() {
+                        body.row(18.0, |mut row| {
+                            row.col(|ui| {
+                                ui.checkbox(&mut item.enable, "");
+                            });
+                            row.col(|ui| {
+                                if ui.button("x").clicked() {
+                                    delete_index = Some(index)
+                                }
+                            });
+                            row.col(|ui| {
+                                ui.text_edit_singleline(&mut item.key);
+                            });
+                            row.col(|ui| {
+                                TextEdit::singleline(&mut item.value)
+                                    .desired_width(f32::INFINITY)
+                                    .ui(ui);
+                            });
+                        });
+                    }
+                    body.row(18.0, |mut row| {
+                        row.col(|ui| {
+                            ui.add_enabled(false, Checkbox::new(&mut self.new_item.enable, ""));
+                        });
+                        row.col(|ui| {
+                            ui.add_enabled(false, Button::new("x"));
+                        });
+                        row.col(|ui| {
+                            ui.text_edit_singleline(&mut self.new_item.key);
+                        });
+                        row.col(|ui| {
+                            TextEdit::singleline(&mut self.new_item.value)
+                                .desired_width(f32::INFINITY)
+                                .ui(ui);
+                        });
+                    });
+                });
+        });
+        if let Some(index) = delete_index {
+            envs.items.remove(index);
+        }
+        if self.new_item.key != "" || self.new_item.value != "" {
+            self.new_item.enable = true;
+            envs.items.push(self.new_item.clone());
+            self.new_item.key = "".to_string();
+            self.new_item.value = "".to_string();
+            self.new_item.enable = false;
+        }
+    }
+}
@@ -1,13 +1,18 @@
+use std::sync::Arc;
+
 use egui::{Image, TextBuffer};
 use uuid::Uuid;
 
-use netpurr_core::data::http::{Header, Response};
+use netpurr_core::data::http::{ContentEncoding, Header, HttpBody, Response};
 
 use crate::operation::operation::Operation;
 use crate::windows::view_json_windows::ViewJsonWindows;
 
 #[derive(Default)]
-pub struct ResponseBodyPanel {}
+pub struct ResponseBodyPanel {
+    show_decoded: bool,
+    decoded: Option<(Arc<HttpBody>, Result<Vec<u8>, String>)>,
+}
 
 impl ResponseBodyPanel {
     pub fn set_and_render(
@@ -27,17 +32,47 @@ impl ResponseBodyPanel {
             layout_job.wrap.max_width = wrap_width;
             ui.fonts(|f| f.layout_job(layout_job))
         };
+        ui.horizontal(|ui| {
+            if ui.button("Save Response").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .set_title("Save Response")
+                    .set_file_name("response")
+                    .save_file()
+                {
+                    match response.body.save_to(path) {
+                        Ok(_) => operation.add_success_toast("Save response success"),
+                        Err(e) => operation.add_error_toast(e.to_string()),
+                    }
+                }
+            }
+            if response.body.content_encoding != ContentEncoding::NONE {
+                ui.checkbox(&mut self.show_decoded, "Show Decoded");
+            }
+            if response.body.is_truncated() {
+                ui.label(format!(
+                    "Response is too large ({}), only a preview is shown",
+                    response.body.get_byte_size()
+                ));
+            }
+        });
+        let bytes = match self.get_body_bytes(response) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                ui.label(format!("Decode response failed: {}", e));
+                return;
+            }
+        };
         match self.get_response_content_type(response) {
             None => {}
             Some(content_type) => {
                 if content_type.value.starts_with("image") {
                     let image = Image::from_bytes(
                         response.request.get_url_with_schema(),
-                        response.body.to_vec(),
+                        bytes,
                     );
                     ui.add(image);
                 } else {
-                    match String::from_utf8(response.body.to_vec()) {
+                    match String::from_utf8(bytes) {
                         Ok(s) => {
                             ui.horizontal(|ui| {
                                 let tooltip = "Click to copy the response body";
@@ -79,6 +114,21 @@ impl ResponseBodyPanel {
             }
         }
     }
+    // The raw bytes are kept when decompression is disabled, decoding is only done on demand
+    // and cached for the response being shown.
+    fn get_body_bytes(&mut self, response: &Response) -> Result<Vec<u8>, String> {
+        if !self.show_decoded || response.body.content_encoding == ContentEncoding::NONE {
+            return Ok(response.body.to_vec());
+        }
+        match &self.decoded {
+            Some((body, decoded)) if Arc::ptr_eq(body, &response.body) => decoded.clone(),
+            _ => {
+                let decoded = response.body.to_decoded_vec().map_err(|e| e.to_string());
+                self.decoded = Some((response.body.clone(), decoded.clone()));
+                decoded
+            }
+        }
+    }
     pub fn get_response_content_type(&self, response: &Response) -> Option<Header> {
         response
             .headers
@@ -5,7 +5,7 @@ use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, EnumString};
 
 use netpurr_core::data::cookies_manager::Cookie;
-use netpurr_core::data::http::{Response, ResponseStatus};
+use netpurr_core::data::http::{ContentEncoding, HttpBody, Response, ResponseStatus};
 use netpurr_core::data::test::{TestResult, TestStatus};
 use netpurr_core::data::workspace_data::WorkspaceData;
 use netpurr_core::runner::TestRunResult;
@@ -144,7 +144,8 @@ impl ResponsePanel {
                 ),
                 TestStatus::WAIT => HighlightValue::None,
                 TestStatus::SKIP => HighlightValue::None,
-                TestStatus::RUNNING => HighlightValue::None
+                TestStatus::RUNNING => HighlightValue::None,
+                TestStatus::CANCELLED => HighlightValue::None,
             },
         }
     }
@@ -178,6 +179,31 @@ impl ResponsePanel {
                     .color(ui.visuals().warn_fg_color)
                     .strong(),
             );
+            if response.body.content_encoding != ContentEncoding::NONE {
+                ui.label("Encoding:");
+                ui.label(
+                    RichText::new(response.body.content_encoding.to_string())
+                        .color(ui.visuals().warn_fg_color)
+                        .strong(),
+                );
+                if let Some(decoded_size) = response.body.decoded_size {
+                    ui.label("Decoded Size:");
+                    ui.label(
+                        RichText::new(HttpBody::format_byte_size(decoded_size))
+                            .color(ui.visuals().warn_fg_color)
+                            .strong(),
+                    );
+                }
+            }
+
+            if !response.http_version.is_empty() {
+                ui.label("Version:");
+                ui.label(
+                    RichText::new(response.http_version.clone())
+                        .color(ui.visuals().warn_fg_color)
+                        .strong(),
+                );
+            }
         });
 
         ui.horizontal(|ui| {
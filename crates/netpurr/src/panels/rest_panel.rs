@@ -1,14 +1,19 @@
-use egui::{Button, Ui, Widget};
+use std::sync::Arc;
+
+use egui::{Button, CollapsingHeader, Ui, Widget};
 use egui::ahash::HashSet;
 use poll_promise::Promise;
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, EnumString};
 
 use netpurr_core::data::auth::{Auth, AuthType};
-use netpurr_core::data::http::{BodyType, HttpRecord, LockWith, Method};
+use netpurr_core::data::http::{
+    BodyType, HttpBody, HttpProtocol, HttpRecord, LockWith, Method, ResponseDownload,
+};
 use netpurr_core::data::test::TestStatus;
 use netpurr_core::data::workspace_data::WorkspaceData;
 use netpurr_core::runner::{RunRequestInfo, TestRunError, TestRunResult};
+use tokio_util::sync::CancellationToken;
 
 use crate::data::config_data::ConfigData;
 use crate::operation::operation::Operation;
@@ -18,6 +23,7 @@ use crate::panels::request_body_panel::RequestBodyPanel;
 use crate::panels::request_headers_panel::RequestHeadersPanel;
 use crate::panels::request_params_panel::RequestParamsPanel;
 use crate::panels::request_pre_script_panel::RequestPreScriptPanel;
+use crate::panels::request_variables_panel::RequestVariablesPanel;
 use crate::panels::test_script_panel::TestScriptPanel;
 use crate::utils;
 use crate::utils::HighlightValue;
@@ -33,7 +39,10 @@ pub struct RestPanel {
     request_body_panel: RequestBodyPanel,
     request_pre_script_panel: RequestPreScriptPanel,
     test_script_panel: TestScriptPanel,
+    request_variables_panel: RequestVariablesPanel,
     send_promise: Option<Promise<Result<TestRunResult, TestRunError>>>,
+    download: Option<ResponseDownload>,
+    cancel_token: Option<CancellationToken>,
 }
 
 #[derive(Clone, EnumIter, EnumString, Display, PartialEq)]
@@ -42,6 +51,8 @@ enum RequestPanelEnum {
     Authorization,
     Headers,
     Body,
+    Variables,
+    Settings,
 }
 
 impl Default for RequestPanelEnum {
@@ -77,6 +88,7 @@ impl RestPanel {
                 );
                 self.render_editor_left_panel(workspace_data, crt_id.clone(), ui);
             });
+            self.render_script_changes(workspace_data, crt_id.clone(), ui);
             ui.separator();
             self.render_middle_select(operation, workspace_data, crt_id.clone(), ui);
             ui.separator();
@@ -127,6 +139,16 @@ impl RestPanel {
                     }
                 }
             },
+            RequestPanelEnum::Variables => {
+                HighlightValue::Usize(hr.envs.items.iter().filter(|i| i.enable).count())
+            }
+            RequestPanelEnum::Settings => {
+                if hr.request.disable_decompression {
+                    HighlightValue::Has
+                } else {
+                    HighlightValue::None
+                }
+            }
         }
     }
 
@@ -148,7 +170,24 @@ impl RestPanel {
                 ui.horizontal(|ui| {
                     ui.add_space(HORIZONTAL_GAP);
                     if self.send_promise.is_some() {
-                        ui.add_enabled(false, Button::new("Send"));
+                        if let Some(download) = &self.download {
+                            if ui.button("Cancel").clicked() {
+                                self.cancel_token.as_ref().map(|t| t.cancel());
+                            }
+                            let state = download.get_state();
+                            if state.received > 0 {
+                                match state.total {
+                                    None => ui.label(HttpBody::format_byte_size(state.received)),
+                                    Some(total) => ui.label(format!(
+                                        "{} / {}",
+                                        HttpBody::format_byte_size(state.received),
+                                        HttpBody::format_byte_size(total as usize)
+                                    )),
+                                };
+                            }
+                        } else {
+                            ui.add_enabled(false, Button::new("Send"));
+                        }
                     } else {
                         if ui.button("Send").clicked() {
                             crt = workspace_data.must_get_mut_crt(crt_id.clone(), |crt| {
@@ -156,6 +195,11 @@ impl RestPanel {
                                     .must_get_mut_rest()
                                     .prepare_send(envs.clone(), parent_auth.clone());
                             });
+                            let download =
+                                ResponseDownload::new(workspace_data.get_response_cache_dir());
+                            self.download = Some(download.clone());
+                            let cancel_token = CancellationToken::new();
+                            self.cancel_token = Some(cancel_token.clone());
                             let send_response =
                                 operation.send_rest_with_script_promise(RunRequestInfo {
                                     shared_map: Default::default(),
@@ -166,6 +210,18 @@ impl RestPanel {
                                     pre_request_scripts: vec![],
                                     test_scripts: vec![],
                                     testcase: Default::default(),
+                                    websocket_scenario: None,
+                                    download,
+                                    cancel_token,
+                                    openapi: workspace_data
+                                        .get_collection(crt.collection_path.clone())
+                                        .and_then(|c| c.openapi)
+                                        .map(Arc::new),
+                                    operation_id: crt.record.must_get_rest().operation_id.clone(),
+                                    lib_dir: Some(workspace_data.get_script_lib_dir()),
+                                    workspace_dir: Some(workspace_data.get_workspace_dir()),
+                                    script_limits: workspace_data.get_script_limits(),
+                                    environment: workspace_data.get_env_select(),
                                 });
                             self.send_promise = Some(send_response);
                             send_rest = Some(crt.record.clone());
@@ -230,6 +286,19 @@ impl RestPanel {
                                     );
                                 }
                             });
+                        egui::ComboBox::from_id_source("protocol")
+                            .selected_text(crt.record.must_get_rest().request.protocol.to_string())
+                            .show_ui(ui, |ui| {
+                                ui.style_mut().wrap = Some(false);
+                                ui.set_min_width(60.0);
+                                for x in HttpProtocol::iter() {
+                                    ui.selectable_value(
+                                        &mut crt.record.must_get_mut_rest().request.protocol,
+                                        x.clone(),
+                                        x.to_string(),
+                                    );
+                                }
+                            });
                         let mut filter: HashSet<String> = HashSet::default();
                         filter.insert(" ".to_string());
                         ui.centered_and_justified(|ui| {
@@ -296,6 +365,22 @@ impl RestPanel {
                 workspace_data,
                 crt_id.clone(),
             ),
+            RequestPanelEnum::Variables => {
+                self.request_variables_panel
+                    .set_and_render(ui, workspace_data, crt_id.clone())
+            }
+            RequestPanelEnum::Settings => {
+                workspace_data.must_get_mut_crt(crt_id.clone(), |crt| {
+                    let request = &mut crt.record.must_get_mut_rest().request;
+                    let mut decompress = !request.disable_decompression;
+                    if ui
+                        .checkbox(&mut decompress, "Automatically decompress the response")
+                        .changed()
+                    {
+                        request.disable_decompression = !decompress;
+                    }
+                });
+            }
         }
     }
 
@@ -309,8 +394,17 @@ impl RestPanel {
         if let Some(promise) = &self.send_promise {
             if let Some(result) = promise.ready() {
                 workspace_data.save_cookies();
+                workspace_data.script_error =
+                    result.as_ref().err().and_then(|e| e.script_error.clone());
+                match result {
+                    Ok(r) => workspace_data
+                        .apply_env_changes(r.collection_path.clone(), r.env_changes.clone()),
+                    Err(e) => workspace_data
+                        .apply_env_changes(e.collection_path.clone(), e.env_changes.clone()),
+                }
                 workspace_data.must_get_mut_crt(crt_id.clone(), |crt| match result {
                     Ok(test_run_result) => {
+                        crt.script_changes = test_run_result.script_changes.clone();
                         test_run_result
                             .request
                             .headers
@@ -323,7 +417,25 @@ impl RestPanel {
                                     .headers
                                     .push(h.clone());
                             });
+                        test_run_result
+                            .request
+                            .params
+                            .iter()
+                            .filter(|q| q.lock_with == LockWith::LockWithScript)
+                            .for_each(|q| {
+                                crt.record
+                                    .must_get_mut_rest()
+                                    .request
+                                    .params
+                                    .push(q.clone());
+                            });
                         match &test_run_result.response {
+                            None if test_run_result.test_result.status
+                                == TestStatus::CANCELLED =>
+                            {
+                                crt.record.must_get_mut_rest().none();
+                                operation.add_success_toast("Request cancelled.");
+                            }
                             None => {
                                 crt.record.must_get_mut_rest().error();
                                 operation.add_error_toast("Send request failed: Response is none".to_string());
@@ -344,12 +456,14 @@ impl RestPanel {
                                     TestStatus::WAIT => {}
                                     TestStatus::SKIP => {}
                                     TestStatus::RUNNING => {}
+                                    TestStatus::CANCELLED => {}
                                 }
                             }
                         }
 
                     }
                     Err(e) => {
+                        crt.script_changes = Default::default();
                         crt.record.must_get_mut_rest().error();
                         operation.add_error_toast(format!(
                             "Send request failed: {}",
@@ -358,6 +472,8 @@ impl RestPanel {
                     }
                 });
                 self.send_promise = None;
+                self.download = None;
+                self.cancel_token = None;
             } else {
                 ui.ctx().request_repaint();
                 workspace_data.must_get_mut_crt(crt_id.clone(), |crt| {
@@ -367,6 +483,87 @@ impl RestPanel {
         }
     }
 
+    fn render_script_changes(
+        &self,
+        workspace_data: &mut WorkspaceData,
+        crt_id: String,
+        ui: &mut Ui,
+    ) {
+        let crt = workspace_data.must_get_crt(crt_id);
+        let changes = &crt.script_changes;
+        if changes.is_empty() {
+            return;
+        }
+        CollapsingHeader::new(format!(
+            "{} Sent with pre-request script changes",
+            egui_phosphor::regular::LOCK
+        ))
+        .id_source("rest_script_changes")
+        .show(ui, |ui| {
+            egui::Grid::new("rest_script_changes_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    if let Some(method) = &changes.method {
+                        ui.label("Method");
+                        ui.label(method.to_string());
+                        ui.end_row();
+                    }
+                    if let Some((schema, base_url)) = &changes.url {
+                        ui.label("URL");
+                        ui.label(format!(
+                            "{}://{}",
+                            schema.to_string().to_lowercase(),
+                            base_url
+                        ));
+                        ui.end_row();
+                    }
+                    if let Some(path_variables) = &changes.path_variables {
+                        ui.label("Path Variables");
+                        ui.label(
+                            path_variables
+                                .iter()
+                                .map(|p| format!("{}={}", p.key, p.value))
+                                .collect::<Vec<String>>()
+                                .join(", "),
+                        );
+                        ui.end_row();
+                    }
+                    for (name, form) in [
+                        ("Form Data", &changes.body_form_data),
+                        ("Form Urlencoded", &changes.body_xxx_form),
+                    ] {
+                        if !form.is_empty() {
+                            ui.label(name);
+                            ui.label(
+                                form.iter()
+                                    .map(|d| format!("{}={}", d.key, d.value))
+                                    .collect::<Vec<String>>()
+                                    .join(", "),
+                            );
+                            ui.end_row();
+                        }
+                    }
+                    if let Some(auth) = &changes.auth {
+                        ui.label("Authorization");
+                        ui.label(auth.auth_type.to_string());
+                        ui.end_row();
+                    }
+                    if let Some((body_type, body_str)) = &changes.body {
+                        ui.label("Body");
+                        ui.label(body_type.to_string());
+                        ui.end_row();
+                        ui.label("");
+                        ui.add(
+                            egui::TextEdit::multiline(&mut body_str.as_str())
+                                .desired_rows(3)
+                                .code_editor(),
+                        );
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
     fn render_middle_select(
         &mut self,
         operation: &Operation,
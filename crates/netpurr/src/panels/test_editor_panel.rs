@@ -77,6 +77,12 @@ impl TestEditorPanel {
         if self.run_promise.is_some() {
             ui.ctx().request_repaint();
         }
+        if let Some(test_group_run_result) = &self.test_group_run_result {
+            let env_changes = test_group_run_result.write().unwrap().take_env_changes();
+            for (collection_path, change) in env_changes {
+                workspace_data.apply_env_changes(collection_path, vec![change]);
+            }
+        }
         workspace_data.selected_test_item.clone().map(|test_item| {
             self.render_test_item_folder(operation, workspace_data, ui, test_item.clone());
             ui.horizontal(|ui|{
@@ -93,25 +99,23 @@ impl TestEditorPanel {
             match self.open_panel_enum {
                 Panel::Runner => {
                     match &test_item {
-                        TestItem::Folder(collection_name, folder) => {
+                        TestItem::Folder(_, folder) => {
                             self.render_select_testcase(workspace_data, ui);
                             self.render_run_folder(
                                 operation,
                                 workspace_data,
                                 ui,
-                                collection_name.clone(),
                                 &folder,
                             );
                             self.render_result_tree(workspace_data, ui, folder.clone());
                         }
-                        TestItem::Record(collection_name, folder,record_name) => {
+                        TestItem::Record(_, folder,record_name) => {
                             self.render_select_testcase(workspace_data, ui);
                             let record = folder.borrow().requests[record_name].clone();
                             self.render_run_record(
                                 operation,
                                 workspace_data,
                                 ui,
-                                collection_name.clone(),
                                 folder.clone(),
                                 record,
                             );
@@ -125,12 +129,12 @@ impl TestEditorPanel {
                 Panel::PreRequestScript => {
                     ui.strong("Pre-request Script:");
                     self.request_pre_script_panel
-                        .set_and_render(ui, workspace_data, test_item.clone());
+                        .set_and_render(ui, operation, workspace_data, test_item.clone());
                 }
                 Panel::TestScript => {
                     ui.strong("Test Script:");
                     self.test_script_panel
-                        .set_and_render(ui, workspace_data, test_item.clone())
+                        .set_and_render(ui, operation, workspace_data, test_item.clone())
                 }
             }
         });
@@ -270,6 +274,7 @@ impl TestEditorPanel {
                             workspace_data.selected_test_run_result = Some(result.clone());
                         }
                         Err(err) => {
+                            workspace_data.script_error = err.script_error.clone();
                             if err.response.is_some() {
                                 workspace_data.selected_test_run_result = Some(TestRunResult {
                                     request: err.request.clone(),
@@ -278,6 +283,8 @@ impl TestEditorPanel {
                                     collection_path: err.collection_path.clone(),
                                     request_name: err.request_name.clone(),
                                     testcase: Default::default(),
+                                    env_changes: vec![],
+                                    script_changes: Default::default(),
                                 });
                             }
                         }
@@ -313,7 +320,6 @@ impl TestEditorPanel {
         operation: &Operation,
         workspace_data: &mut WorkspaceData,
         ui: &mut Ui,
-        collection_name: String,
         folder: &Rc<RefCell<CollectionFolder>>,
     ) {
         ui.horizontal(|ui|{
@@ -327,7 +333,6 @@ impl TestEditorPanel {
                         workspace_data,
                         operation,
                         test_group_run_result,
-                        collection_name.clone(),
                         folder.borrow().get_path(),
                         self.build_parent_testcase(),
                         folder.clone(),
@@ -365,11 +370,10 @@ impl TestEditorPanel {
         operation: &Operation,
         workspace_data: &mut WorkspaceData,
         ui: &mut Ui,
-        collection_name: String,
         parent_folder: Rc<RefCell<CollectionFolder>>,
         record: Record,
     ) {
-        ui.add_enabled_ui(self.run_promise.is_none(), |ui| {
+        if self.run_promise.is_none() {
             if ui.button("Run Test").clicked() {
                 let test_group_run_result = Arc::new(RwLock::new(TestGroupRunResults::default()));
                 self.test_group_run_result = Some(test_group_run_result.clone());
@@ -377,13 +381,18 @@ impl TestEditorPanel {
                     workspace_data,
                     operation,
                     test_group_run_result,
-                    collection_name,
                     parent_folder.borrow().get_path(),
                     self.build_parent_testcase(),
                     record.clone(),
                 );
             }
-        });
+        } else {
+            if ui.button("Stop Test").clicked() {
+                if let Some(r) = &self.test_group_run_result {
+                    r.write().unwrap().stop();
+                }
+            }
+        }
     }
     fn render_manager_testcase(
         &mut self,
@@ -590,6 +599,7 @@ impl TestEditorPanel {
                         workspace_data.selected_test_run_result = Some(test_result.clone())
                     }
                     Err(err) => {
+                        workspace_data.script_error = err.script_error.clone();
                         if err.response.is_some() {
                             workspace_data.selected_test_run_result = Some(TestRunResult {
                                 request: err.request.clone(),
@@ -598,6 +608,8 @@ impl TestEditorPanel {
                                 collection_path: err.collection_path.clone(),
                                 request_name: err.request_name.clone(),
                                 testcase: Default::default(),
+                                env_changes: vec![],
+                                script_changes: Default::default(),
                             });
                         }
                     }
@@ -624,6 +636,7 @@ impl TestEditorPanel {
             TestStatus::WAIT => rich_text = rich_text.background_color(Color32::DARK_GRAY),
             TestStatus::SKIP => rich_text = rich_text.background_color(Color32::GRAY),
             TestStatus::RUNNING => rich_text = rich_text.background_color(Color32::DARK_BLUE),
+            TestStatus::CANCELLED => rich_text = rich_text.background_color(Color32::BROWN),
         };
         rich_text.append_to(
             &mut request_test_result_name_layout_job,
@@ -676,13 +689,11 @@ impl TestEditorPanel {
         workspace_data: &mut WorkspaceData,
         operation: &Operation,
         test_group_run_result: Arc<RwLock<TestGroupRunResults>>,
-        collection_name: String,
         collection_path: String,
         parent_testcase: Option<Testcase>,
         folder: Rc<RefCell<CollectionFolder>>,
     ) {
-        let envs = workspace_data
-            .get_build_envs(workspace_data.get_collection(Some(collection_name.clone())));
+        let envs = workspace_data.get_path_envs(Some(collection_path.clone()));
         let script_tree = workspace_data.get_script_tree(collection_path.clone());
         self.run_promise = Some(operation.run_test_group_promise(
             fast,
@@ -700,13 +711,11 @@ impl TestEditorPanel {
         workspace_data: &mut WorkspaceData,
         operation: &Operation,
         test_group_run_result: Arc<RwLock<TestGroupRunResults>>,
-        collection_name: String,
         collection_path: String,
         parent_testcase: Option<Testcase>,
         record: Record,
     ) {
-        let envs = workspace_data
-            .get_build_envs(workspace_data.get_collection(Some(collection_name.clone())));
+        let envs = workspace_data.get_path_envs(Some(collection_path.clone()));
         let script_tree = workspace_data.get_script_tree(collection_path.clone());
         self.run_promise = Some(operation.run_test_record_promise(
             envs,
@@ -9,7 +9,9 @@ use egui_code_editor::{CodeEditor, ColorTheme, Prompt};
 use netpurr_core::data::collections::CollectionFolder;
 use netpurr_core::data::workspace_data::{TestItem, WorkspaceData};
 
+use crate::operation::operation::Operation;
 use crate::widgets::syntax::js_syntax;
+use crate::windows::script_library_windows::ScriptLibraryWindows;
 
 #[derive(Default)]
 pub struct TestScriptPanel {}
@@ -23,6 +25,7 @@ impl TestScriptPanel {
     pub fn set_and_render(
         &mut self,
         ui: &mut Ui,
+        operation: &Operation,
         workspace_data: &mut WorkspaceData,
         test_item: TestItem,
     ) {
@@ -96,6 +99,15 @@ impl TestScriptPanel {
                                 if ui.link("Sleep").clicked() {
                                     script = script.clone().add("\nawait sleep(1000);");
                                 }
+                                for lib in workspace_data.get_script_libs() {
+                                    if ui.link(format!("Import {}", lib)).clicked() {
+                                        let stem = lib.trim_end_matches(".js").replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+                                        script = format!("import * as {} from \"lib:{}\";\n{}", stem, lib, script);
+                                    }
+                                }
+                                if ui.link("Manage script library").clicked() {
+                                    operation.add_window(Box::new(ScriptLibraryWindows::default()));
+                                }
                                 if ui.link("Get response").clicked() {
                                     script = script.clone().add("\nlet response = netpurr.resp();\nconsole.log(response)");
                                 }
@@ -103,6 +115,7 @@ impl TestScriptPanel {
                         });
                     });
             });
+        let error_line = workspace_data.script_error_line(&test_item, script.as_str());
         egui::ScrollArea::vertical()
             .max_height(ui.available_height()-30.0)
             .id_source("test_manager_test_script")
@@ -114,6 +127,7 @@ impl TestScriptPanel {
                     .with_ui_fontsize(ui)
                     .with_prompt(Prompt::from_str(prompt_yaml))
                     .with_syntax(js_syntax())
+                    .with_error_line(error_line)
                     .with_numlines(true);
                 if ui.visuals().dark_mode {
                     code_editor = code_editor.with_theme(ColorTheme::GRUVBOX)
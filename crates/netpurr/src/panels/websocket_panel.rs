@@ -5,7 +5,7 @@ use strum_macros::{Display, EnumIter, EnumString};
 use url::Url;
 
 use netpurr_core::data::auth::{Auth, AuthType};
-use netpurr_core::data::http::HttpRecord;
+use netpurr_core::data::record::Record;
 use netpurr_core::data::websocket::WebSocketStatus;
 use netpurr_core::data::workspace_data::WorkspaceData;
 
@@ -16,8 +16,10 @@ use crate::panels::HORIZONTAL_GAP;
 use crate::panels::request_headers_panel::RequestHeadersPanel;
 use crate::panels::request_params_panel::RequestParamsPanel;
 use crate::panels::request_pre_script_panel::RequestPreScriptPanel;
+use crate::panels::request_variables_panel::RequestVariablesPanel;
 use crate::panels::websocket_content_panel::WebsocketContentPanel;
 use crate::panels::websocket_event_panel::WebsocketEventPanel;
+use crate::panels::websocket_scenario_panel::WebsocketScenarioPanel;
 use crate::utils;
 use crate::utils::HighlightValue;
 use crate::widgets::highlight_template::HighlightTemplateSinglelineBuilder;
@@ -32,6 +34,8 @@ pub struct WebSocketPanel {
     request_headers_panel: RequestHeadersPanel,
     request_pre_script_panel: RequestPreScriptPanel,
     websocket_event_panel: WebsocketEventPanel,
+    websocket_scenario_panel: WebsocketScenarioPanel,
+    request_variables_panel: RequestVariablesPanel,
 }
 
 #[derive(Clone, EnumIter, EnumString, Display, PartialEq)]
@@ -40,6 +44,8 @@ enum RequestPanelEnum {
     Params,
     Authorization,
     Headers,
+    Scenario,
+    Variables,
 }
 
 impl Default for RequestPanelEnum {
@@ -84,10 +90,11 @@ impl WebSocketPanel {
         self.toast_event(operation, workspace_data, &crt_id);
     }
     fn get_count(
-        hr: &HttpRecord,
+        record: &Record,
         panel_enum: RequestPanelEnum,
         parent_auth: &Auth,
     ) -> HighlightValue {
+        let hr = record.must_get_rest();
         match panel_enum {
             RequestPanelEnum::Params => {
                 HighlightValue::Usize(hr.request.params.iter().filter(|i| i.enable).count())
@@ -104,6 +111,12 @@ impl WebSocketPanel {
                 HighlightValue::Usize(hr.request.headers.iter().filter(|i| i.enable).count())
             }
             RequestPanelEnum::Content => HighlightValue::None,
+            RequestPanelEnum::Scenario => {
+                HighlightValue::Usize(record.must_get_websocket().scenario.len())
+            }
+            RequestPanelEnum::Variables => {
+                HighlightValue::Usize(hr.envs.items.iter().filter(|i| i.enable).count())
+            }
         }
     }
     fn render_editor_right_panel(
@@ -117,6 +130,7 @@ impl WebSocketPanel {
         let (pre_request_parent_script_scopes, mut test_parent_script_scopes) =
             workspace_data.get_crt_parent_scripts(crt_id.clone());
         let envs = workspace_data.get_crt_envs(crt_id.clone());
+        let environment = workspace_data.get_env_select();
         let mut crt = workspace_data.must_get_crt(crt_id.clone());
         egui::SidePanel::right("editor_right_panel")
             .resizable(false)
@@ -162,6 +176,7 @@ impl WebSocketPanel {
                                                     Some(operation.connect_websocket_with_script(
                                                         crt.record.must_get_rest().request.clone(),
                                                         envs,
+                                                        environment,
                                                         pre_request_parent_script_scopes,
                                                         test_parent_script_scopes,
                                                     ));
@@ -312,6 +327,14 @@ impl WebSocketPanel {
                 workspace_data,
                 crt_id.clone(),
             ),
+            RequestPanelEnum::Scenario => {
+                self.websocket_scenario_panel
+                    .set_and_render(ui, workspace_data, crt_id.clone())
+            }
+            RequestPanelEnum::Variables => {
+                self.request_variables_panel
+                    .set_and_render(ui, workspace_data, crt_id.clone())
+            }
         }
     }
     fn render_middle_select(
@@ -334,7 +357,7 @@ impl WebSocketPanel {
                             x.clone(),
                             utils::build_with_count_ui_header(
                                 x.to_string(),
-                                Self::get_count(crt.record.must_get_rest(), x, &parent_auth),
+                                Self::get_count(&crt.record, x, &parent_auth),
                                 ui,
                             ),
                         );
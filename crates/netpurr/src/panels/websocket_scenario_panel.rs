@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use eframe::emath::Align;
+use egui::{Button, DragValue, Layout, Widget};
+use egui_extras::{Column, TableBody, TableBuilder};
+use strum::IntoEnumIterator;
+
+use netpurr_core::data::central_request_data::CentralRequestItem;
+use netpurr_core::data::environment::EnvironmentItemValue;
+use netpurr_core::data::websocket::{
+    MessageType, WebSocketMatchType, WebSocketScenarioStep, WebSocketStepType,
+};
+use netpurr_core::data::workspace_data::WorkspaceData;
+
+use crate::widgets::highlight_template::HighlightTemplateSinglelineBuilder;
+
+#[derive(Default)]
+pub struct WebsocketScenarioPanel {}
+
+impl WebsocketScenarioPanel {
+    pub fn set_and_render(
+        &mut self,
+        ui: &mut egui::Ui,
+        workspace_data: &mut WorkspaceData,
+        crt_id: String,
+    ) {
+        let envs = workspace_data.get_crt_envs(crt_id.clone());
+        workspace_data.must_get_mut_crt(crt_id.clone(), |crt| {
+            ui.horizontal(|ui| {
+                ui.label("Scenario");
+                if ui.button("+ Send").clicked() {
+                    crt.record
+                        .must_get_mut_websocket()
+                        .scenario
+                        .push(WebSocketScenarioStep::default());
+                }
+                if ui.button("+ Expect").clicked() {
+                    crt.record
+                        .must_get_mut_websocket()
+                        .scenario
+                        .push(WebSocketScenarioStep {
+                            step_type: WebSocketStepType::Expect,
+                            ..Default::default()
+                        });
+                }
+            });
+            let mut delete_index = None;
+            ui.push_id("websocket_scenario_table", |ui| {
+                let table = TableBuilder::new(ui)
+                    .resizable(false)
+                    .cell_layout(Layout::left_to_right(Align::Center))
+                    .column(Column::exact(20.0))
+                    .column(Column::exact(70.0))
+                    .column(Column::exact(90.0))
+                    .column(Column::initial(250.0).range(40.0..=400.0))
+                    .column(Column::initial(150.0).range(40.0..=300.0))
+                    .column(Column::remainder())
+                    .max_scroll_height(200.0);
+                table
+                    .header(20.0, |mut header| {
+                        header.col(|ui| {
+                            ui.strong("");
+                        });
+                        header.col(|ui| {
+                            ui.strong("STEP");
+                        });
+                        header.col(|ui| {
+                            ui.strong("TYPE");
+                        });
+                        header.col(|ui| {
+                            ui.strong("CONTENT");
+                        });
+                        header.col(|ui| {
+                            ui.strong("EXPECT VALUE");
+                        });
+                        header.col(|ui| {
+                            ui.strong("TIMEOUT(MS)");
+                        });
+                    })
+                    .body(|mut body| {
+                        delete_index = self.build_body(crt, &envs, &mut body);
+                    });
+            });
+            if let Some(index) = delete_index {
+                crt.record.must_get_mut_websocket().scenario.remove(index);
+            }
+        });
+    }
+
+    fn build_body(
+        &self,
+        data: &mut CentralRequestItem,
+        envs: &BTreeMap<String, EnvironmentItemValue>,
+        body: &mut TableBody,
+    ) -> Option<usize> {
+        let mut delete_index = None;
+        for (index, step) in data
+            .record
+            .must_get_mut_websocket()
+            .scenario
+            .iter_mut()
+            .enumerate()
+        {
+            body.row(18.0, |mut row| {
+                row.col(|ui| {
+                    if ui.add(Button::new("x")).clicked() {
+                        delete_index = Some(index)
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(step.step_type.to_string());
+                });
+                row.col(|ui| match step.step_type {
+                    WebSocketStepType::Send => {
+                        egui::ComboBox::from_id_source(
+                            "scenario_message_type_".to_string() + index.to_string().as_str(),
+                        )
+                        .selected_text(step.message_type.to_string())
+                        .show_ui(ui, |ui| {
+                            ui.style_mut().wrap = Some(false);
+                            ui.set_min_width(60.0);
+                            for x in MessageType::iter() {
+                                ui.selectable_value(&mut step.message_type, x.clone(), x.to_string());
+                            }
+                        });
+                    }
+                    WebSocketStepType::Expect => {
+                        egui::ComboBox::from_id_source(
+                            "scenario_match_type_".to_string() + index.to_string().as_str(),
+                        )
+                        .selected_text(step.match_type.to_string())
+                        .show_ui(ui, |ui| {
+                            ui.style_mut().wrap = Some(false);
+                            ui.set_min_width(60.0);
+                            for x in WebSocketMatchType::iter() {
+                                ui.selectable_value(&mut step.match_type, x.clone(), x.to_string());
+                            }
+                        });
+                    }
+                });
+                row.col(|ui| {
+                    HighlightTemplateSinglelineBuilder::default()
+                        .envs(envs.clone())
+                        .all_space(false)
+                        .build(
+                            "scenario_content_".to_string() + index.to_string().as_str(),
+                            &mut step.content,
+                        )
+                        .ui(ui);
+                });
+                row.col(|ui| {
+                    ui.add_enabled_ui(
+                        step.step_type == WebSocketStepType::Expect
+                            && step.match_type == WebSocketMatchType::JsonPath,
+                        |ui| {
+                            HighlightTemplateSinglelineBuilder::default()
+                                .envs(envs.clone())
+                                .all_space(false)
+                                .build(
+                                    "scenario_expect_value_".to_string()
+                                        + index.to_string().as_str(),
+                                    &mut step.expect_value,
+                                )
+                                .ui(ui);
+                        },
+                    );
+                });
+                row.col(|ui| {
+                    ui.add_enabled(
+                        step.step_type == WebSocketStepType::Expect,
+                        DragValue::new(&mut step.timeout).speed(100),
+                    );
+                });
+            });
+        }
+        delete_index
+    }
+}
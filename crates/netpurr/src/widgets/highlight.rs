@@ -5,6 +5,7 @@ use egui::{Color32, Ui};
 use regex::Regex;
 
 use netpurr_core::data::environment::EnvironmentItemValue;
+use netpurr_core::utils;
 
 pub fn highlight_template(
     mut text: &str,
@@ -28,8 +29,7 @@ pub fn highlight_template(
     let mut start = 0;
     for x in re.find_iter(text) {
         job.append(&text[start..x.range().start], 0.0, normal_format.clone());
-        let key = x.as_str().trim_start_matches("{{").trim_end_matches("}}");
-        if envs.contains_key(key) {
+        if utils::check_variable(x.as_str(), &envs).is_empty() {
             job.append(
                 &text[x.range().start..x.range().end],
                 0.0,
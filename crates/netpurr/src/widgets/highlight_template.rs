@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use eframe::emath::pos2;
 use egui::{
@@ -8,9 +8,14 @@ use egui::{
 use egui::ahash::HashSet;
 use egui::text::{CCursor, CCursorRange, CursorRange};
 use egui::text_edit::TextEditState;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use netpurr_core::data::environment::EnvironmentItemValue;
+use netpurr_core::data::environment::{
+    secret_values, EnvironmentItemValue, EnvironmentValueType, SECRET_MASK,
+    VARIABLE_PRECEDENCE,
+};
+use netpurr_core::data::logger::mask_secrets;
 
 use crate::panels::VERTICAL_GAP;
 use crate::utils;
@@ -166,7 +171,12 @@ impl HighlightTemplate<'_> {
                 ui.vertical(|ui| {
                     ui.horizontal_wrapped(|ui| {
                         ui.strong("VALUE");
-                        ui.label(self.envs.get(&key).unwrap().value.clone())
+                        let value = self.envs.get(&key).unwrap();
+                        if value.value_type == EnvironmentValueType::Secret {
+                            ui.label(SECRET_MASK)
+                        } else {
+                            ui.label(value.value.clone())
+                        }
                     });
                     ui.add_space(VERTICAL_GAP);
                     ui.horizontal(|ui| {
@@ -178,6 +188,7 @@ impl HighlightTemplate<'_> {
                         ui.strong("SCOPE");
                         ui.label(self.envs.get(&key).unwrap().scope.clone())
                     });
+                    ui.small(VARIABLE_PRECEDENCE);
                 });
             });
         });
@@ -185,6 +196,14 @@ impl HighlightTemplate<'_> {
     }
 }
 
+// The keys of the `{{key ?? default | filter}}` templates in `content`.
+fn template_keys(content: &str) -> BTreeSet<String> {
+    let re = Regex::new(r"\{\{(.*?)}}").unwrap();
+    re.captures_iter(content)
+        .filter_map(|c| c[1].split(|c| c == '|' || c == '?').next().map(|k| k.trim().to_string()))
+        .collect()
+}
+
 impl Widget for HighlightTemplate<'_> {
     fn ui(self, ui: &mut Ui) -> Response {
         let mut layouter = |ui: &Ui, string: &str, wrap_width: f32| {
@@ -211,12 +230,20 @@ impl Widget for HighlightTemplate<'_> {
         let mut output = text_edit.show(ui);
         ui.set_enabled(true);
         let mut response = output.response.clone();
-        let text = netpurr_core::utils::replace_variable(
-            self.content.as_str().to_string(),
-            self.envs.clone(),
-        );
-        if response.hovered() && text.len() > 0 && text != self.content.as_str() &&self.quick_render {
-            response = response.on_hover_text(text);
+        let (text, errors) =
+            netpurr_core::utils::resolve_variable(self.content.as_str(), &self.envs);
+        if response.hovered() && !errors.is_empty() {
+            let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            response = response.on_hover_text(errors.join("\n"));
+        } else if response.hovered() && text.len() > 0 && text != self.content.as_str() &&self.quick_render {
+            let secrets = secret_values(&self.envs);
+            let mut hover_text = mask_secrets(text.as_str(), &secrets);
+            for key in template_keys(self.content.as_str()) {
+                if let Some(value) = self.envs.get(&key) {
+                    hover_text += format!("\n{} from {}", key, value.scope).as_str();
+                }
+            }
+            response = response.on_hover_text(hover_text);
         }
         output.cursor_range.map(|c| {
             let hts_state = HTSState {
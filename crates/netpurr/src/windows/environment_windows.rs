@@ -1,7 +1,15 @@
-use egui::{Align, Button, Checkbox, Layout, ScrollArea, TextEdit, Ui, Widget};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use egui::{Align, Button, Checkbox, Layout, RichText, ScrollArea, TextEdit, Ui, Widget};
 use egui_extras::{Column, TableBuilder};
+use strum::IntoEnumIterator;
 
-use netpurr_core::data::environment::{ENVIRONMENT_GLOBALS, EnvironmentConfig, EnvironmentItem};
+use netpurr_core::data::environment::{
+    ENVIRONMENT_GLOBALS, EnvironmentConfig, EnvironmentFileType, EnvironmentItem,
+    EnvironmentValueType, FileEncoding, SECRET_MASK,
+};
 use netpurr_core::data::workspace_data::WorkspaceData;
 
 use crate::data::config_data::ConfigData;
@@ -17,6 +25,9 @@ pub struct EnvironmentWindows {
     select_env_config: EnvironmentConfig,
     select_env_name: String,
     new_select_env_item: EnvironmentItem,
+    compare: bool,
+    bulk_key: String,
+    bulk_value: String,
 }
 
 impl Window for EnvironmentWindows {
@@ -45,16 +56,20 @@ impl Window for EnvironmentWindows {
         operation: Operation,
     ) {
         if self.select_env.is_none() {
-            self.env_list(workspace_data, ui);
+            if self.compare {
+                self.env_matrix(workspace_data, &operation, ui);
+            } else {
+                self.env_list(workspace_data, &operation, ui);
+            }
         } else {
-            self.select_modify(ui);
+            self.select_modify(workspace_data, &operation, ui);
         }
-        self.env_bottom(workspace_data, ui);
+        self.env_bottom(workspace_data, &operation, ui);
     }
 }
 
 impl EnvironmentWindows {
-    fn env_list(&mut self, workspace_data: &mut WorkspaceData, ui: &mut Ui) {
+    fn env_list(&mut self, workspace_data: &mut WorkspaceData, operation: &Operation, ui: &mut Ui) {
         ui.label("An environment is a set of variables that allow you to switch the context of your requests. Environments can be shared between multiple workspaces.");
         ui.add_space(VERTICAL_GAP);
         ScrollArea::vertical().show(ui, |ui| {
@@ -79,7 +94,14 @@ impl EnvironmentWindows {
                                 if ui.button("📋").clicked() {
                                     workspace_data.add_env(name.to_string() + " Copy", e.clone());
                                 };
-                                ui.button("⬇");
+                                ui.menu_button("⬇", |ui| {
+                                    for file_type in EnvironmentFileType::iter() {
+                                        if ui.button(file_type.to_string()).clicked() {
+                                            ui.close_menu();
+                                            Self::export_env(name, e, &file_type, operation);
+                                        }
+                                    }
+                                });
                                 if ui.button("🗑").clicked() {
                                     workspace_data.remove_env(name.to_string());
                                 }
@@ -91,7 +113,187 @@ impl EnvironmentWindows {
         });
     }
 
-    fn select_modify(&mut self, ui: &mut Ui) {
+    fn export_env(
+        name: &String,
+        config: &EnvironmentConfig,
+        file_type: &EnvironmentFileType,
+        operation: &Operation,
+    ) {
+        if let Some(path) = rfd::FileDialog::new()
+            .set_title("Export Environment")
+            .set_file_name(format!("{}.{}", name, file_type.extension()))
+            .save_file()
+        {
+            match config
+                .export(name, file_type)
+                .and_then(|content| Ok(fs::write(path, content)?))
+            {
+                Ok(_) => operation.add_success_toast("Export environment success"),
+                Err(e) => operation.add_error_toast(e.to_string()),
+            }
+        }
+    }
+
+    fn import_env(workspace_data: &mut WorkspaceData, operation: &Operation) {
+        if let Some(path) = rfd::FileDialog::new().pick_file() {
+            let result = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| EnvironmentConfig::import(content.as_str()));
+            match result {
+                Ok((name, config)) => {
+                    let name = name.filter(|n| !n.is_empty()).unwrap_or(
+                        path.file_stem()
+                            .map(|s| s.to_string_lossy().trim_start_matches('.').to_string())
+                            .filter(|s| !s.is_empty())
+                            .unwrap_or("Imported".to_string()),
+                    );
+                    let names = workspace_data.get_env_configs().keys().cloned().collect();
+                    workspace_data.add_env(utils::build_copy_name(name, names), config);
+                    operation.add_success_toast("Import environment success");
+                }
+                Err(e) => operation.add_error_toast(e.to_string()),
+            }
+        }
+    }
+
+    fn env_matrix(&mut self, workspace_data: &mut WorkspaceData, operation: &Operation, ui: &mut Ui) {
+        ui.label("Compare the variables of all environments, missing, empty and disabled values are highlighted. Click a variable to edit it in bulk.");
+        ui.add_space(VERTICAL_GAP);
+        ui.horizontal(|ui| {
+            ui.strong("VARIABLE");
+            TextEdit::singleline(&mut self.bulk_key)
+                .desired_width(120.0)
+                .ui(ui);
+            ui.strong("VALUE / NEW NAME");
+            TextEdit::singleline(&mut self.bulk_value)
+                .desired_width(120.0)
+                .ui(ui);
+            let enable = !self.bulk_key.is_empty();
+            if ui.add_enabled(enable, Button::new("Add to all")).clicked() {
+                workspace_data.add_env_key(self.bulk_key.clone(), self.bulk_value.clone());
+            }
+            if ui
+                .add_enabled(enable && !self.bulk_value.is_empty(), Button::new("Rename"))
+                .clicked()
+            {
+                match workspace_data.rename_env_key(self.bulk_key.clone(), self.bulk_value.clone())
+                {
+                    Ok(_) => {
+                        self.bulk_key = self.bulk_value.clone();
+                        self.bulk_value = "".to_string();
+                    }
+                    Err(e) => operation.add_error_toast(e.to_string()),
+                }
+            }
+            if ui.add_enabled(enable, Button::new("Delete")).clicked() {
+                workspace_data.remove_env_key(self.bulk_key.clone());
+            }
+        });
+        ui.add_space(VERTICAL_GAP);
+        let envs: BTreeMap<String, EnvironmentConfig> = workspace_data
+            .get_env_configs()
+            .into_iter()
+            .filter(|(name, _)| name != ENVIRONMENT_GLOBALS)
+            .collect();
+        let keys: BTreeSet<String> = envs
+            .values()
+            .flat_map(|config| config.items.iter().map(|i| i.key.clone()))
+            .collect();
+        ScrollArea::horizontal().show(ui, |ui| {
+            TableBuilder::new(ui)
+                .resizable(true)
+                .cell_layout(Layout::left_to_right(Align::Center))
+                .column(Column::initial(150.0).clip(true))
+                .columns(Column::initial(120.0).clip(true), envs.len())
+                .max_scroll_height(400.0)
+                .header(20.0, |mut header| {
+                    header.col(|ui| {
+                        ui.strong("VARIABLE");
+                    });
+                    for name in envs.keys() {
+                        header.col(|ui| {
+                            ui.strong(name);
+                        });
+                    }
+                })
+                .body(|mut body| {
+                    for key in keys.iter() {
+                        body.row(18.0, |mut row| {
+                            row.col(|ui| {
+                                if ui.selectable_label(self.bulk_key == *key, key).clicked() {
+                                    self.bulk_key = key.clone();
+                                }
+                            });
+                            for config in envs.values() {
+                                row.col(|ui| match config.items.iter().find(|i| i.key == *key) {
+                                    None => {
+                                        ui.colored_label(ui.visuals().error_fg_color, "missing");
+                                    }
+                                    Some(item) if item.value.is_empty() => {
+                                        ui.colored_label(ui.visuals().warn_fg_color, "empty");
+                                    }
+                                    Some(item) => {
+                                        let value = if item.value_type == EnvironmentValueType::Secret {
+                                            SECRET_MASK.to_string()
+                                        } else {
+                                            item.value.clone()
+                                        };
+                                        if item.enable {
+                                            ui.label(value);
+                                        } else {
+                                            ui.label(RichText::new(value).strikethrough().weak())
+                                                .on_hover_text("disabled");
+                                        }
+                                    }
+                                });
+                            }
+                        });
+                    }
+                });
+        });
+    }
+
+    // Files outside the workspace are copied into its `files` dir so git sync can track them,
+    // a different file with the same name gets a numbered name instead of overwriting it.
+    fn workspace_file(workspace_dir: &PathBuf, path: &Path) -> anyhow::Result<String> {
+        if let Ok(relative) = path.strip_prefix(workspace_dir) {
+            return Ok(relative.display().to_string());
+        }
+        let stem = path
+            .file_stem()
+            .ok_or(anyhow::Error::msg("not a file"))?
+            .to_string_lossy()
+            .to_string();
+        let extension = path
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        let content = fs::read(path)?;
+        let files_dir = workspace_dir.join("files");
+        fs::create_dir_all(&files_dir)?;
+        let mut file_name = format!("{}{}", stem, extension);
+        let mut index = 2;
+        loop {
+            let target = files_dir.join(&file_name);
+            if !target.exists() {
+                fs::write(target, content)?;
+                break;
+            }
+            if fs::read(&target)? == content {
+                break;
+            }
+            file_name = format!("{} {}{}", stem, index, extension);
+            index += 1;
+        }
+        Ok(format!("files/{}", file_name))
+    }
+
+    fn select_modify(
+        &mut self,
+        workspace_data: &WorkspaceData,
+        operation: &Operation,
+        ui: &mut Ui,
+    ) {
         if self.select_env_name == ENVIRONMENT_GLOBALS {
             ui.label("Global variables for a workspace are a set of variables that are always available within the scope of that workspace. They can be viewed and edited by anyone in that workspace.");
         } else {
@@ -101,6 +303,7 @@ impl EnvironmentWindows {
                 .ui(ui);
         }
         ui.add_space(VERTICAL_GAP);
+        let workspace_dir = workspace_data.get_workspace_dir();
         let mut delete_index = None;
         ui.push_id("environment_table", |ui| {
             let table = TableBuilder::new(ui)
@@ -109,6 +312,7 @@ impl EnvironmentWindows {
                 .column(Column::auto())
                 .column(Column::exact(20.0))
                 .column(Column::initial(200.0).range(40.0..=300.0))
+                .column(Column::auto())
                 .column(Column::remainder())
                 .max_scroll_height(400.0);
             table
@@ -122,6 +326,9 @@ impl EnvironmentWindows {
                     header.col(|ui| {
                         ui.strong("VARIABLE");
                     });
+                    header.col(|ui| {
+                        ui.strong("TYPE");
+                    });
                     header.col(|ui| {
                         ui.strong("VALUE");
                     });
@@ -141,7 +348,56 @@ impl EnvironmentWindows {
                                 ui.text_edit_singleline(&mut item.key);
                             });
                             row.col(|ui| {
+                                if item.value_type == EnvironmentValueType::Function {
+                                    ui.label(item.value_type.to_string());
+                                    return;
+                                }
+                                egui::ComboBox::from_id_source(format!("env_value_type_{}", index))
+                                    .selected_text(item.value_type.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for value_type in [
+                                            EnvironmentValueType::String,
+                                            EnvironmentValueType::Secret,
+                                            EnvironmentValueType::File,
+                                        ] {
+                                            ui.selectable_value(
+                                                &mut item.value_type,
+                                                value_type.clone(),
+                                                value_type.to_string(),
+                                            );
+                                        }
+                                    });
+                            });
+                            row.col(|ui| {
+                                if item.value_type == EnvironmentValueType::File {
+                                    egui::ComboBox::from_id_source(format!(
+                                        "env_file_encoding_{}",
+                                        index
+                                    ))
+                                    .selected_text(item.file_encoding.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for encoding in FileEncoding::iter() {
+                                            ui.selectable_value(
+                                                &mut item.file_encoding,
+                                                encoding.clone(),
+                                                encoding.to_string(),
+                                            );
+                                        }
+                                    });
+                                    if ui.button("Select File").clicked() {
+                                        if let Some(path) = rfd::FileDialog::new().pick_file() {
+                                            match Self::workspace_file(&workspace_dir, &path) {
+                                                Ok(file) => item.value = file,
+                                                Err(e) => operation.add_error_toast(format!(
+                                                    "Select file failed: {}",
+                                                    e
+                                                )),
+                                            }
+                                        }
+                                    }
+                                }
                                 TextEdit::singleline(&mut item.value)
+                                    .password(item.value_type == EnvironmentValueType::Secret)
                                     .desired_width(f32::INFINITY)
                                     .ui(ui);
                             });
@@ -160,6 +416,9 @@ impl EnvironmentWindows {
                         row.col(|ui| {
                             ui.text_edit_singleline(&mut self.new_select_env_item.key);
                         });
+                        row.col(|ui| {
+                            ui.label(self.new_select_env_item.value_type.to_string());
+                        });
                         row.col(|ui| {
                             TextEdit::singleline(&mut self.new_select_env_item.value)
                                 .desired_width(f32::INFINITY)
@@ -182,20 +441,29 @@ impl EnvironmentWindows {
         }
     }
 
-    fn env_bottom(&mut self, workspace_data: &mut WorkspaceData, ui: &mut Ui) {
+    fn env_bottom(&mut self, workspace_data: &mut WorkspaceData, operation: &Operation, ui: &mut Ui) {
         egui::TopBottomPanel::bottom("environment_bottom_panel")
             .resizable(false)
             .min_height(0.0)
             .show_inside(ui, |ui| {
                 ui.add_space(VERTICAL_GAP);
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                    if self.select_env.is_none() {
+                    if self.select_env.is_none() && self.compare {
+                        if ui.button("Back").clicked() {
+                            self.compare = false;
+                        }
+                    } else if self.select_env.is_none() {
                         if ui.button("Add").clicked() {
                             self.select_env = Some("".to_string());
                             self.select_env_config = EnvironmentConfig::default();
                             self.select_env_name = "".to_string();
                         }
-                        ui.button("Import");
+                        if ui.button("Import").clicked() {
+                            Self::import_env(workspace_data, operation);
+                        }
+                        if ui.button("Compare").clicked() {
+                            self.compare = true;
+                        }
                         if ui.button("Globals").clicked() {
                             let data = workspace_data.get_env(ENVIRONMENT_GLOBALS.to_string());
                             self.select_env = Some(ENVIRONMENT_GLOBALS.to_string());
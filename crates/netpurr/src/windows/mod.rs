@@ -6,6 +6,8 @@ pub mod new_collection_windows;
 pub mod request_close_windows;
 pub mod save_crt_windows;
 pub mod save_windows;
+pub mod script_library_windows;
 pub mod test_script_windows;
+pub mod variable_lint_windows;
 pub mod view_json_windows;
 pub mod workspace_windows;
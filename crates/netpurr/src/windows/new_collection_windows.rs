@@ -2,14 +2,13 @@ use std::cell::RefCell;
 use std::default::Default;
 use std::rc::Rc;
 
-use egui::{Align, Button, Checkbox, Layout, TextEdit, Ui, Widget};
-use egui_extras::{Column, TableBuilder};
+use egui::{Align, Layout, Ui};
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, EnumString};
 
 use netpurr_core::data::auth::{Auth, AuthType};
 use netpurr_core::data::collections::{Collection, CollectionFolder};
-use netpurr_core::data::environment::EnvironmentItem;
+use netpurr_core::data::environment::VARIABLE_PRECEDENCE;
 use netpurr_core::data::workspace_data::WorkspaceData;
 
 use crate::data::config_data::ConfigData;
@@ -17,6 +16,7 @@ use crate::operation::operation::Operation;
 use crate::operation::windows::{Window, WindowSetting};
 use crate::panels::auth_panel::AuthPanel;
 use crate::panels::request_pre_script_panel::RequestPreScriptPanel;
+use crate::panels::request_variables_panel::RequestVariablesPanel;
 use crate::panels::test_script_panel::TestScriptPanel;
 use crate::panels::VERTICAL_GAP;
 use crate::utils;
@@ -25,7 +25,6 @@ use crate::utils::HighlightValue;
 #[derive(Default)]
 pub struct NewCollectionWindows {
     title_name: String,
-    new_select_env_item: EnvironmentItem,
     new_collection_windows_open: bool,
     new_collection: Collection,
     old_collection_name: Option<String>,
@@ -36,6 +35,7 @@ pub struct NewCollectionWindows {
     auth_panel: AuthPanel,
     request_pre_script_panel: RequestPreScriptPanel,
     test_script_panel: TestScriptPanel,
+    variables_panel: RequestVariablesPanel,
     search_input: String,
 }
 
@@ -80,13 +80,14 @@ impl Window for NewCollectionWindows {
     ) {
         ui.label("Name");
         utils::text_edit_singleline_filter_justify(ui, &mut self.folder.borrow_mut().name);
+        let vars = match &self.parent_folder {
+            None => self.new_collection.envs.items.len(),
+            Some(_) => self.folder.borrow().envs.items.len(),
+        };
         let parent_auth =
             workspace_data.get_path_parent_auth(self.folder.borrow().parent_path.clone());
         ui.horizontal(|ui| {
             for x in NewCollectionContentType::iter() {
-                if x == NewCollectionContentType::Variables && self.parent_folder.is_some() {
-                    continue;
-                }
                 ui.selectable_value(
                     &mut self.new_collection_content_type,
                     x.clone(),
@@ -96,7 +97,7 @@ impl Window for NewCollectionWindows {
                             self.folder.clone(),
                             x,
                             &parent_auth,
-                            self.new_collection.envs.items.len(),
+                            vars,
                         ),
                         ui,
                     ),
@@ -193,89 +194,27 @@ impl NewCollectionWindows {
     }
 
     fn build_variables(&mut self, ui: &mut Ui) {
-        ui.label("These variables are specific to this collection and its requests. ");
+        match &self.parent_folder {
+            None => ui.label("These variables are specific to this collection and its requests. "),
+            Some(_) => ui.label(
+                "These variables are specific to this folder, its subfolders and its requests. ",
+            ),
+        };
+        ui.small(VARIABLE_PRECEDENCE);
         ui.add_space(VERTICAL_GAP);
         ui.separator();
         ui.add_space(VERTICAL_GAP);
-        let mut delete_index = None;
-        ui.push_id("new_collection_environment_table", |ui| {
-            let table = TableBuilder::new(ui)
-                .resizable(false)
-                .cell_layout(Layout::left_to_right(Align::Center))
-                .column(Column::auto())
-                .column(Column::exact(20.0))
-                .column(Column::initial(200.0).range(40.0..=300.0))
-                .column(Column::remainder())
-                .max_scroll_height(400.0);
-            table
-                .header(20.0, |mut header| {
-                    header.col(|ui| {
-                        ui.strong("");
-                    });
-                    header.col(|ui| {
-                        ui.strong("");
-                    });
-                    header.col(|ui| {
-                        ui.strong("VARIABLE");
-                    });
-                    header.col(|ui| {
-                        ui.strong("VALUE");
-                    });
-                })
-                .body(|mut body| {
-                    for (index, item) in self.new_collection.envs.items.iter_mut().enumerate() {
-                        body.row(18.0, |mut row| {
-                            row.col(|ui| {
-                                ui.checkbox(&mut item.enable, "");
-                            });
-                            row.col(|ui| {
-                                if ui.button("x").clicked() {
-                                    delete_index = Some(index)
-                                }
-                            });
-                            row.col(|ui| {
-                                ui.text_edit_singleline(&mut item.key);
-                            });
-                            row.col(|ui| {
-                                TextEdit::singleline(&mut item.value)
-                                    .desired_width(f32::INFINITY)
-                                    .ui(ui);
-                            });
-                        });
-                    }
-                    body.row(18.0, |mut row| {
-                        row.col(|ui| {
-                            ui.add_enabled(
-                                false,
-                                Checkbox::new(&mut self.new_select_env_item.enable, ""),
-                            );
-                        });
-                        row.col(|ui| {
-                            ui.add_enabled(false, Button::new("x"));
-                        });
-                        row.col(|ui| {
-                            ui.text_edit_singleline(&mut self.new_select_env_item.key);
-                        });
-                        row.col(|ui| {
-                            TextEdit::singleline(&mut self.new_select_env_item.value)
-                                .desired_width(f32::INFINITY)
-                                .ui(ui);
-                        });
-                    });
-                });
-        });
-        if delete_index.is_some() {
-            self.new_collection.envs.items.remove(delete_index.unwrap());
-        }
-        if self.new_select_env_item.key != "" || self.new_select_env_item.value != "" {
-            self.new_select_env_item.enable = true;
-            self.new_collection
-                .envs
-                .items
-                .push(self.new_select_env_item.clone());
-            self.new_select_env_item.key = "".to_string();
-            self.new_select_env_item.value = "".to_string();
-            self.new_select_env_item.enable = false;
+        match &self.parent_folder {
+            None => self.variables_panel.render_table(
+                ui,
+                "new_collection_environment_table",
+                &mut self.new_collection.envs,
+            ),
+            Some(_) => self.variables_panel.render_table(
+                ui,
+                "new_collection_environment_table",
+                &mut self.folder.borrow_mut().envs,
+            ),
         }
     }
 
@@ -197,6 +197,7 @@ impl SaveCRTWindows {
                                         pre_request_script: "".to_string(),
                                         test_script: "".to_string(),
                                         testcases: Default::default(),
+                                        envs: Default::default(),
                                     })),
                                 );
                             }
@@ -231,6 +232,7 @@ impl SaveCRTWindows {
                             pre_request_script: "".to_string(),
                             test_script: "".to_string(),
                             testcases: Default::default(),
+                            envs: Default::default(),
                         })),
                         ..Default::default()
                     });
@@ -227,6 +227,7 @@ impl SaveWindows {
                                         pre_request_script: "".to_string(),
                                         test_script: "".to_string(),
                                         testcases: Default::default(),
+                                        envs: Default::default(),
                                     })),
                                 );
                             }
@@ -261,6 +262,7 @@ impl SaveWindows {
                             pre_request_script: "".to_string(),
                             test_script: "".to_string(),
                             testcases: Default::default(),
+                            envs: Default::default(),
                         })),
                         ..Default::default()
                     });
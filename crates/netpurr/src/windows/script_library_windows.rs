@@ -0,0 +1,129 @@
+use egui::Ui;
+use egui_code_editor::{CodeEditor, ColorTheme, Prompt};
+
+use netpurr_core::data::workspace_data::WorkspaceData;
+
+use crate::data::config_data::ConfigData;
+use crate::operation::operation::Operation;
+use crate::operation::windows::{Window, WindowSetting};
+use crate::panels::VERTICAL_GAP;
+use crate::widgets::syntax::js_syntax;
+
+const NEW_LIB_SCRIPT: &str = r#"// Import it in a script with `import { sign } from "lib:NAME";`
+export function sign(value) {
+    return netpurr.crypto.sha256(value);
+}
+"#;
+
+#[derive(Default)]
+pub struct ScriptLibraryWindows {
+    open: bool,
+    new_name: String,
+    selected: Option<String>,
+    script: String,
+}
+
+impl Window for ScriptLibraryWindows {
+    fn window_setting(&self) -> WindowSetting {
+        WindowSetting::new("SCRIPT LIBRARY")
+            .min_width(700.0)
+            .max_height(600.0)
+    }
+
+    fn set_open(&mut self, open: bool) {
+        self.open = open
+    }
+
+    fn get_open(&self) -> bool {
+        self.open
+    }
+
+    fn render(
+        &mut self,
+        ui: &mut Ui,
+        _: &mut ConfigData,
+        workspace_data: &mut WorkspaceData,
+        operation: Operation,
+    ) {
+        ui.label(format!(
+            "Scripts import these files with `lib:<name>`, they are stored in {:?}.",
+            workspace_data.get_script_lib_dir()
+        ));
+        ui.add_space(VERTICAL_GAP);
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_name);
+            if ui.button("New").clicked() {
+                let mut name = self.new_name.trim().to_string();
+                if !name.ends_with(".js") {
+                    name = name + ".js";
+                }
+                if workspace_data.get_script_libs().contains(&name) {
+                    operation.add_error_toast(format!("`{}` already exists", name));
+                } else {
+                    let script = NEW_LIB_SCRIPT.replace("NAME", name.as_str());
+                    match workspace_data.save_script_lib(name.as_str(), script.as_str()) {
+                        Ok(_) => {
+                            self.new_name.clear();
+                            self.script = script;
+                            self.selected = Some(name);
+                        }
+                        Err(e) => operation.add_error_toast(e.to_string()),
+                    }
+                }
+            }
+        });
+        ui.separator();
+        egui::SidePanel::left("script_library_list")
+            .resizable(false)
+            .max_width(180.0)
+            .show_inside(ui, |ui| {
+                for name in workspace_data.get_script_libs() {
+                    let selected = self.selected.as_ref() == Some(&name);
+                    if ui.selectable_label(selected, name.as_str()).clicked() && !selected {
+                        self.script = workspace_data.read_script_lib(name.as_str());
+                        self.selected = Some(name);
+                    }
+                }
+            });
+        let Some(name) = self.selected.clone() else {
+            ui.label("Select a library script or create a new one.");
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.strong(name.as_str());
+            if ui.button("Save").clicked() {
+                match workspace_data.save_script_lib(name.as_str(), self.script.as_str()) {
+                    Ok(_) => operation.add_success_toast("Save success"),
+                    Err(e) => operation.add_error_toast(e.to_string()),
+                }
+            }
+            if ui.button("Delete").clicked() {
+                match workspace_data.remove_script_lib(name.as_str()) {
+                    Ok(_) => {
+                        self.selected = None;
+                        self.script.clear();
+                    }
+                    Err(e) => operation.add_error_toast(e.to_string()),
+                }
+            }
+        });
+        let prompt_yaml = include_str!("../../prompt/js.yaml");
+        let mut code_editor = CodeEditor::default()
+            .id_source("script_library_code_editor")
+            .with_rows(25)
+            .with_ui_fontsize(ui)
+            .with_syntax(js_syntax())
+            .with_prompt(Prompt::from_str(prompt_yaml))
+            .with_numlines(true);
+        if ui.visuals().dark_mode {
+            code_editor = code_editor.with_theme(ColorTheme::GRUVBOX)
+        } else {
+            code_editor = code_editor.with_theme(ColorTheme::GRUVBOX_LIGHT)
+        }
+        egui::ScrollArea::vertical()
+            .id_source("script_library_script")
+            .show(ui, |ui| {
+                code_editor.show(ui, &mut self.script);
+            });
+    }
+}
@@ -0,0 +1,129 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use egui::{Align, Layout, Ui};
+use egui_extras::{Column, TableBuilder};
+
+use netpurr_core::data::collections::{Collection, CollectionFolder};
+use netpurr_core::data::lint;
+use netpurr_core::data::lint::VariableLintReport;
+use netpurr_core::data::workspace_data::WorkspaceData;
+
+use crate::data::config_data::ConfigData;
+use crate::operation::operation::Operation;
+use crate::operation::windows::{Window, WindowSetting};
+use crate::panels::VERTICAL_GAP;
+
+#[derive(Default)]
+pub struct VariableLintWindows {
+    open: bool,
+    collection: Collection,
+    folder: Rc<RefCell<CollectionFolder>>,
+    report: Option<VariableLintReport>,
+    show_all: bool,
+}
+
+impl VariableLintWindows {
+    pub fn with(mut self, collection: Collection, folder: Rc<RefCell<CollectionFolder>>) -> Self {
+        self.collection = collection;
+        self.folder = folder;
+        self
+    }
+}
+
+impl Window for VariableLintWindows {
+    fn window_setting(&self) -> WindowSetting {
+        WindowSetting::new_with_id("LINT VARIABLES", self.folder.borrow().get_path())
+            .min_width(600.0)
+            .max_height(500.0)
+    }
+
+    fn set_open(&mut self, open: bool) {
+        self.open = open
+    }
+
+    fn get_open(&self) -> bool {
+        self.open
+    }
+
+    fn render(
+        &mut self,
+        ui: &mut Ui,
+        _: &mut ConfigData,
+        workspace_data: &mut WorkspaceData,
+        _: Operation,
+    ) {
+        let report = self.report.get_or_insert_with(|| {
+            let envs = workspace_data.get_path_envs(Some(self.folder.borrow().get_path()));
+            lint::lint_folder(self.folder.clone(), &envs)
+        });
+        let unresolved = report.unresolved();
+        ui.horizontal(|ui| {
+            if unresolved.is_empty() {
+                ui.label(format!(
+                    "{} variables referenced by {}, all resolved with the selected environment.",
+                    report.keys().len(),
+                    self.folder.borrow().get_path()
+                ));
+            } else {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!(
+                        "{} of {} references can not be resolved with the selected environment.",
+                        unresolved.len(),
+                        report.usages.len()
+                    ),
+                );
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.show_all, "Show resolved");
+            if ui.button("Refresh").clicked() {
+                self.report = None;
+            }
+        });
+        ui.add_space(VERTICAL_GAP);
+        let usages = match (&self.report, self.show_all) {
+            (Some(report), true) => report.usages.clone(),
+            _ => unresolved,
+        };
+        TableBuilder::new(ui)
+            .resizable(true)
+            .cell_layout(Layout::left_to_right(Align::Center))
+            .column(Column::initial(200.0).clip(true))
+            .column(Column::initial(120.0).clip(true))
+            .column(Column::initial(100.0).clip(true))
+            .column(Column::remainder())
+            .max_scroll_height(400.0)
+            .header(20.0, |mut header| {
+                for title in ["PATH", "LOCATION", "VARIABLE", "ERROR"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|mut body| {
+                for usage in usages.iter() {
+                    body.row(18.0, |mut row| {
+                        row.col(|ui| {
+                            ui.label(usage.path.as_str()).on_hover_text(usage.path.as_str());
+                        });
+                        row.col(|ui| {
+                            ui.label(usage.location.as_str());
+                        });
+                        row.col(|ui| {
+                            ui.label(usage.key.as_str());
+                        });
+                        row.col(|ui| match &usage.error {
+                            None => {
+                                ui.label("-");
+                            }
+                            Some(error) => {
+                                ui.colored_label(ui.visuals().error_fg_color, error);
+                            }
+                        });
+                    });
+                }
+            });
+    }
+}
@@ -54,7 +54,7 @@ impl Window for WorkspaceWindows {
         &mut self,
         ui: &mut Ui,
         config_data: &mut ConfigData,
-        _: &mut WorkspaceData,
+        workspace_data: &mut WorkspaceData,
         operation: Operation,
     ) {
         self.render_left_panel(ui, config_data);
@@ -81,6 +81,8 @@ impl Window for WorkspaceWindows {
                                 utils::text_edit_singleline_justify(ui, &mut path);
                             });
                             ui.separator();
+                            self.render_script_limits(ui, workspace, workspace_data);
+                            ui.separator();
                             match &self.current_workspace_git_repo {
                                 None => {
                                     if ui.button("Enable Git").clicked() {
@@ -96,7 +98,12 @@ impl Window for WorkspaceWindows {
                                                 || self.force_pull_promise.is_some()
                                                 || self.force_push_promise.is_some();
                                             ui.add_enabled_ui(!lock, |ui| {
-                                                self.sync_button(ui, workspace, &operation);
+                                                self.sync_button(
+                                                    ui,
+                                                    workspace,
+                                                    workspace_data,
+                                                    &operation,
+                                                );
                                                 self.force_pull_button(ui, workspace, &operation);
                                                 self.force_push(ui, workspace, &operation);
                                             });
@@ -114,6 +121,33 @@ impl Window for WorkspaceWindows {
 }
 
 impl WorkspaceWindows {
+    fn render_script_limits(
+        &mut self,
+        ui: &mut Ui,
+        workspace: &Workspace,
+        workspace_data: &WorkspaceData,
+    ) {
+        ui.strong("Script Limits");
+        if workspace.name != workspace_data.workspace_name {
+            ui.label("Open this workspace to edit its script limits.");
+            return;
+        }
+        let mut script_limits = workspace_data.get_script_limits();
+        ui.horizontal(|ui| {
+            ui.label("Timeout (ms): ");
+            ui.add(egui::DragValue::new(&mut script_limits.timeout_ms).speed(100))
+                .on_hover_text("Wall-clock time of a pre-request or test script run, 0 means no limit.");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Heap limit (MB): ");
+            ui.add(egui::DragValue::new(&mut script_limits.max_heap_mb))
+                .on_hover_text("Maximum memory of the script runtime, 0 means no limit.");
+        });
+        if script_limits != workspace_data.get_script_limits() {
+            workspace_data.set_script_limits(script_limits);
+        }
+    }
+
     fn force_push(&mut self, ui: &mut Ui, workspace: &Workspace, operation: &Operation) {
         let button = ui.button("Force Push");
         button.clone().on_hover_text(
@@ -168,14 +202,26 @@ impl WorkspaceWindows {
         }
     }
 
-    fn sync_button(&mut self, ui: &mut Ui, workspace: &Workspace, operation: &Operation) {
+    fn sync_button(
+        &mut self,
+        ui: &mut Ui,
+        workspace: &Workspace,
+        workspace_data: &WorkspaceData,
+        operation: &Operation,
+    ) {
         let button = ui.button("Sync");
         button.clone().on_hover_text(
             "Synchronize data to remote git, it will automatically `commit`, `rebase` and `push`",
         );
         if button.clicked() {
             self.status = "Waiting ...".to_string();
-            self.sync_promise = Some(operation.git().git_sync_promise(workspace.path.clone()));
+            let files = if workspace.name == workspace_data.workspace_name {
+                workspace_data.get_env_files().into_iter().collect()
+            } else {
+                vec![]
+            };
+            self.sync_promise =
+                Some(operation.git().git_sync_promise(workspace.path.clone(), files));
         }
         if let Some(promise) = &self.sync_promise {
             Spinner::new().ui(ui);
@@ -0,0 +1,240 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use deno_core::JsRuntime;
+
+use netpurr_core::data::http::Request;
+use netpurr_core::runner::{HttpClients, RunRequestInfo, Runner};
+use netpurr_core::script::{Context, JsResponse, ScriptLimits, ScriptRuntime, ScriptScope};
+use tokio_util::sync::CancellationToken;
+
+const STARTUP_ROUNDS: usize = 200;
+const CHAIN_ROUNDS: usize = 200;
+const CHAIN_SCOPES: usize = 4;
+const COLLECTION_REQUESTS: usize = 500;
+
+// cargo bench -p netpurr_core --bench script_runtime
+fn main() {
+    bench_isolate_startup();
+    bench_scope_chain();
+    bench_collection_run();
+}
+
+/// Compares the old per-scope startup (fresh isolate + runtime.js) with the snapshot.
+fn bench_isolate_startup() {
+    let start = Instant::now();
+    for _ in 0..STARTUP_ROUNDS {
+        let mut js_runtime = JsRuntime::new(Default::default());
+        js_runtime
+            .execute_script_static(
+                "[runjs:runtime.js]",
+                include_str!("../src/script/resource/runtime.js"),
+            )
+            .unwrap();
+    }
+    let cold = start.elapsed();
+    let start = Instant::now();
+    for _ in 0..STARTUP_ROUNDS {
+        let _js_runtime = ScriptRuntime::build_js_runtime(None, &ScriptLimits::default());
+    }
+    let snapshot = start.elapsed();
+    println!("isolate startup x{}", STARTUP_ROUNDS);
+    print_result("runtime.js", cold, STARTUP_ROUNDS);
+    print_result("snapshot", snapshot, STARTUP_ROUNDS);
+    println!(
+        "  speedup: {:.1}x",
+        cold.as_secs_f64() / snapshot.as_secs_f64()
+    );
+}
+
+/// Compares a chain of scopes sharing one isolate with the cold-start baseline, where every
+/// scope builds its own isolate.
+fn bench_scope_chain() {
+    let scripts: Vec<ScriptScope> = (0..CHAIN_SCOPES)
+        .map(|level| ScriptScope {
+            script: format!("netpurr.set_env(\"level{}\", \"{}\");", level, level),
+            scope: format!("folder{}", level),
+        })
+        .collect();
+    let tokio_runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let start = Instant::now();
+    for _ in 0..CHAIN_ROUNDS {
+        for script in scripts.iter() {
+            tokio_runtime
+                .block_on(ScriptRuntime::run_async(
+                    vec![script.clone()],
+                    Context::default(),
+                    CancellationToken::new(),
+                ))
+                .unwrap();
+        }
+    }
+    let cold = start.elapsed();
+    let start = Instant::now();
+    for _ in 0..CHAIN_ROUNDS {
+        tokio_runtime
+            .block_on(ScriptRuntime::run_async(
+                scripts.clone(),
+                Context::default(),
+                CancellationToken::new(),
+            ))
+            .unwrap();
+    }
+    let shared = start.elapsed();
+    println!("scope chain of {} x{}", CHAIN_SCOPES, CHAIN_ROUNDS);
+    print_result("cold start", cold, CHAIN_ROUNDS);
+    print_result("shared", shared, CHAIN_ROUNDS);
+    println!(
+        "  speedup: {:.1}x",
+        cold.as_secs_f64() / shared.as_secs_f64()
+    );
+}
+
+/// Runs a collection against a local mock server, every request has three folder
+/// pre-request scripts and a test script. The cold-start baseline runs every scope in its own
+/// isolate around a script-less request.
+fn bench_collection_run() {
+    let address = start_mock_server();
+    let clients = HttpClients::new(None);
+    let pre_request_scripts: Vec<ScriptScope> = (0..3)
+        .map(|level| ScriptScope {
+            script: format!("netpurr.set_env(\"level{}\", \"{}\");", level, level),
+            scope: format!("folder{}", level),
+        })
+        .collect();
+    let test_scripts = vec![ScriptScope {
+        script: "netpurr.test(\"status\", () => assert(200, netpurr.resp().status));"
+            .to_string(),
+        scope: "request".to_string(),
+    }];
+    let tokio_runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let start = Instant::now();
+    for index in 0..COLLECTION_REQUESTS {
+        let mut context = Context::default();
+        for script in pre_request_scripts.iter() {
+            context = tokio_runtime
+                .block_on(ScriptRuntime::run_async(
+                    vec![script.clone()],
+                    context,
+                    CancellationToken::new(),
+                ))
+                .unwrap();
+        }
+        let mut run_request_info = collection_request(&address, index, vec![], vec![]);
+        run_request_info.envs = context.envs.clone();
+        let result = tokio_runtime
+            .block_on(Runner::run_request_info_async(run_request_info, clients.clone()));
+        let response = result
+            .ok()
+            .and_then(|r| r.response)
+            .unwrap_or_else(|| panic!("request {} failed", index));
+        context.response = JsResponse::from_data_response(response);
+        for script in test_scripts.iter() {
+            context = tokio_runtime
+                .block_on(ScriptRuntime::run_async(
+                    vec![script.clone()],
+                    context,
+                    CancellationToken::new(),
+                ))
+                .unwrap();
+        }
+    }
+    let cold = start.elapsed();
+    let start = Instant::now();
+    for index in 0..COLLECTION_REQUESTS {
+        let run_request_info = collection_request(
+            &address,
+            index,
+            pre_request_scripts.clone(),
+            test_scripts.clone(),
+        );
+        let result = tokio_runtime
+            .block_on(Runner::run_request_info_async(run_request_info, clients.clone()));
+        assert!(result.is_ok(), "request {} failed", index);
+    }
+    let snapshot = start.elapsed();
+    println!("collection run x{}", COLLECTION_REQUESTS);
+    print_result("cold start", cold, COLLECTION_REQUESTS);
+    print_result("snapshot", snapshot, COLLECTION_REQUESTS);
+    println!(
+        "  speedup: {:.1}x",
+        cold.as_secs_f64() / snapshot.as_secs_f64()
+    );
+}
+
+fn collection_request(
+    address: &str,
+    index: usize,
+    pre_request_scripts: Vec<ScriptScope>,
+    test_scripts: Vec<ScriptScope>,
+) -> RunRequestInfo {
+    RunRequestInfo {
+        shared_map: Default::default(),
+        collection_path: None,
+        request_name: format!("request{}", index),
+        request: Request {
+            raw_url: format!("http://{}/ping", address),
+            base_url: format!("{}/ping", address),
+            ..Default::default()
+        },
+        envs: Default::default(),
+        pre_request_scripts,
+        test_scripts,
+        testcase: Default::default(),
+        websocket_scenario: None,
+        download: Default::default(),
+        cancel_token: Default::default(),
+        openapi: None,
+        operation_id: None,
+        lib_dir: None,
+        workspace_dir: None,
+        script_limits: Default::default(),
+        environment: None,
+    }
+}
+
+fn print_result(name: &str, total: Duration, rounds: usize) {
+    println!(
+        "  {:<12} total {:>8.1} ms, {:>6.2} ms/iter",
+        name,
+        total.as_secs_f64() * 1000.0,
+        total.as_secs_f64() * 1000.0 / rounds as f64
+    );
+}
+
+fn start_mock_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || handle_connection(stream));
+        }
+    });
+    address
+}
+
+fn handle_connection(mut stream: TcpStream) {
+    let mut request = vec![];
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => request.extend_from_slice(&buffer[..n]),
+        }
+    }
+    let body = "{\"ok\":true}";
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
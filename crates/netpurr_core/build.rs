@@ -0,0 +1,27 @@
+use std::env;
+use std::path::PathBuf;
+
+use deno_core::snapshot_util::{create_snapshot, CreateSnapshotOptions};
+
+// Bake runtime.js into a V8 startup snapshot so script runs don't have to
+// execute it again for every new isolate.
+fn main() {
+    println!("cargo:rerun-if-changed=src/script/resource/runtime.js");
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    create_snapshot(CreateSnapshotOptions {
+        cargo_manifest_dir: env!("CARGO_MANIFEST_DIR"),
+        snapshot_path: out_dir.join("NETPURR_RUNTIME_SNAPSHOT.bin"),
+        startup_snapshot: None,
+        skip_op_registration: false,
+        extensions: vec![],
+        compression_cb: None,
+        with_runtime_cb: Some(Box::new(|js_runtime| {
+            js_runtime
+                .execute_script_static(
+                    "[runjs:runtime.js]",
+                    include_str!("src/script/resource/runtime.js"),
+                )
+                .unwrap();
+        })),
+    });
+}
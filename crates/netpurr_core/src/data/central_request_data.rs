@@ -4,6 +4,7 @@ use std::path::Path;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::data::http::ScriptRequestChanges;
 use crate::data::record::Record;
 use crate::data::record::Record::{Rest, WebSocket};
 use crate::data::test::TestResult;
@@ -157,6 +158,9 @@ pub struct CentralRequestItem {
     pub record: Record,
     pub test_result: TestResult,
     pub modify_baseline: String,
+    /// What the scripts changed in the last sent request, shown until the next send.
+    #[serde(skip)]
+    pub script_changes: ScriptRequestChanges,
 }
 
 impl CentralRequestItem {
@@ -1,5 +1,5 @@
 use std::cell::RefCell;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
@@ -229,6 +229,23 @@ impl Collections {
         }
         (pre_scripts, test_scripts)
     }
+    /// Layers the variables of the folders along `path` over `envs`, the inner folders last.
+    pub fn layer_path_envs(&self, path: String, envs: &mut BTreeMap<String, EnvironmentItemValue>) {
+        let mut name_builder = Vec::new();
+        for path_part in path.split("/") {
+            name_builder.push(path_part);
+            if name_builder.len() == 1 {
+                continue;
+            }
+            self.get_folder_with_path(name_builder.join("/"))
+                .1
+                .map(|f| {
+                    f.borrow()
+                        .envs
+                        .layer_over(envs, name_builder.join("/") + " Folder");
+                });
+        }
+    }
     pub fn get_auth(&self, path: String) -> Auth {
         let (_, of) = self.get_folder_with_path(path.clone());
         let binding = path.clone();
@@ -308,6 +325,7 @@ impl Default for Collection {
                 pre_request_script: "".to_string(),
                 test_script: "".to_string(),
                 testcases: Default::default(),
+                envs: Default::default(),
             })),
         }
     }
@@ -328,16 +346,8 @@ impl Collection {
     }
     pub fn build_envs(&self) -> BTreeMap<String, EnvironmentItemValue> {
         let mut result = BTreeMap::default();
-        for item in self.envs.items.iter().filter(|i| i.enable) {
-            result.insert(
-                item.key.clone(),
-                EnvironmentItemValue {
-                    value: item.value.to_string(),
-                    scope: self.folder.borrow().name.clone() + " Collection",
-                    value_type: item.value_type.clone(),
-                },
-            );
-        }
+        self.envs
+            .layer_over(&mut result, self.folder.borrow().name.clone() + " Collection");
         result
     }
     fn save_info(&self, persistence: Persistence, path: PathBuf) {
@@ -387,6 +397,7 @@ pub struct CollectionFolder {
     pub pre_request_script: String,
     pub test_script: String,
     pub testcases: BTreeMap<String, Testcase>,
+    pub envs: EnvironmentConfig,
 }
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(default)]
@@ -439,6 +450,7 @@ pub struct CollectionFolderOnlyRead {
     pub pre_request_script: String,
     pub test_script: String,
     pub testcases: BTreeMap<String, Testcase>,
+    pub envs: EnvironmentConfig,
 }
 impl CollectionFolderOnlyRead {
     pub fn get_path(&self) -> String {
@@ -463,6 +475,7 @@ pub struct SaveCollectionFolder {
     pub pre_request_script: String,
     pub test_script: String,
     pub testcases: BTreeMap<String, Testcase>,
+    pub envs: EnvironmentConfig,
 }
 
 impl CollectionFolderOnlyRead {
@@ -486,6 +499,7 @@ impl CollectionFolder {
             pre_request_script: self.pre_request_script.clone(),
             test_script: self.test_script.clone(),
             testcases: self.testcases.clone(),
+            envs: self.envs.clone(),
         }
     }
     pub fn load(&mut self, persistence: Persistence, path: PathBuf) {
@@ -513,6 +527,7 @@ impl CollectionFolder {
             self.pre_request_script = cf.pre_request_script;
             self.test_script = cf.test_script;
             self.testcases = cf.testcases;
+            self.envs = cf.envs;
         });
         for item in persistence.load_list(path.clone()).iter() {
             if item.is_file() {
@@ -553,6 +568,16 @@ impl CollectionFolder {
         self.save_info(persistence.clone(), path.clone());
     }
 
+    pub fn collect_env_files(&self, files: &mut BTreeSet<String>) {
+        files.extend(self.envs.files());
+        for record in self.requests.values() {
+            files.extend(record.envs().files());
+        }
+        for folder in self.folders.values() {
+            folder.borrow().collect_env_files(files);
+        }
+    }
+
     pub fn fix_path_recursion(&mut self, parent_path: String) {
         self.parent_path = parent_path;
         for (_, f) in self.folders.iter_mut() {
@@ -260,16 +260,24 @@ impl Cookie {
         let s = raw.split(";");
         for (index, x) in s.into_iter().enumerate() {
             let one: Vec<&str> = x.splitn(2, "=").collect();
+            // attribute names are case-insensitive and `Secure`/`HttpOnly` carry no value
+            let attribute = one[0].trim().to_lowercase();
+            if index > 0 && attribute == "secure" {
+                cookie.secure = true;
+                continue;
+            }
+            if index > 0 && attribute == "httponly" {
+                cookie.http_only = true;
+                continue;
+            }
             if one.len() < 2 {
                 continue;
             }
-            match one[0].trim() {
-                "expires" => cookie.expires = one[1].to_string(),
-                "path" => cookie.path = one[1].to_string(),
-                "domain" => cookie.domain = one[1].to_string(),
-                "max-age" => cookie.max_age = one[1].to_string(),
-                "secure" => cookie.secure = true,
-                "httponly" => cookie.http_only = true,
+            match attribute.as_str() {
+                "expires" if index > 0 => cookie.expires = one[1].to_string(),
+                "path" if index > 0 => cookie.path = one[1].to_string(),
+                "domain" if index > 0 => cookie.domain = one[1].to_string(),
+                "max-age" if index > 0 => cookie.max_age = one[1].to_string(),
                 _ => {
                     if index == 0 {
                         cookie.value = one[1].to_string();
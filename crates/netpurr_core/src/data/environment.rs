@@ -4,7 +4,7 @@ use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
-use strum_macros::Display;
+use strum_macros::{Display, EnumIter};
 
 use crate::data::collections::Collection;
 use crate::data::environment_function::EnvFunction;
@@ -35,96 +35,29 @@ impl Environment {
         &self,
         collection: Option<Collection>,
     ) -> BTreeMap<String, EnvironmentItemValue> {
-        self.status.select.clone().map_or_else(
-            || {
-                let mut result = BTreeMap::default();
-                self.get(ENVIRONMENT_GLOBALS.to_string()).map(|e| {
-                    for et in e.items.iter().filter(|i| i.enable) {
-                        result.insert(
-                            et.key.clone(),
-                            EnvironmentItemValue {
-                                value: et.value.clone(),
-                                scope: ENVIRONMENT_GLOBALS.to_string(),
-                                value_type: et.value_type.clone(),
-                            },
-                        );
-                    }
-                });
-                collection.clone().map(|c| {
-                    for et in c.envs.items.iter().filter(|item| item.enable) {
-                        result.insert(
-                            et.key.clone(),
-                            EnvironmentItemValue {
-                                value: et.value.clone(),
-                                scope: c.folder.borrow().name.clone() + " Collection",
-                                value_type: et.value_type.clone(),
-                            },
-                        );
-                    }
-                });
-                for ef in EnvFunction::iter() {
-                    result.insert(
-                        "$".to_string() + ef.to_string().as_str(),
-                        EnvironmentItemValue {
-                            value: ef.to_string(),
-                            scope: "Global".to_string(),
-                            value_type: EnvironmentValueType::Function,
-                        },
-                    );
-                }
-                result
-            },
-            |s| {
-                let mut result = BTreeMap::default();
-                self.get(ENVIRONMENT_GLOBALS.to_string()).map(|e| {
-                    for et in e.items.iter().filter(|i| i.enable) {
-                        result.insert(
-                            et.key.clone(),
-                            EnvironmentItemValue {
-                                value: et.value.clone(),
-                                scope: ENVIRONMENT_GLOBALS.to_string(),
-                                value_type: et.value_type.clone(),
-                            },
-                        );
-                    }
-                });
-                self.get(s.clone()).map(|e| {
-                    for et in e.items.iter().filter(|i| i.enable) {
-                        result.insert(
-                            et.key.clone(),
-                            EnvironmentItemValue {
-                                value: et.value.clone(),
-                                scope: s.clone(),
-                                value_type: et.value_type.clone(),
-                            },
-                        );
-                    }
-                });
-                collection.clone().map(|c| {
-                    for et in c.envs.items.iter().filter(|item| item.enable) {
-                        result.insert(
-                            et.key.clone(),
-                            EnvironmentItemValue {
-                                value: et.value.clone(),
-                                scope: c.folder.borrow().name.clone() + " Collection",
-                                value_type: et.value_type.clone(),
-                            },
-                        );
-                    }
-                });
-                for ef in EnvFunction::iter() {
-                    result.insert(
-                        "$".to_string() + ef.to_string().as_str(),
-                        EnvironmentItemValue {
-                            value: ef.to_string(),
-                            scope: "Global".to_string(),
-                            value_type: EnvironmentValueType::Function,
-                        },
-                    );
-                }
-                result
-            },
-        )
+        let mut result = BTreeMap::default();
+        self.get(ENVIRONMENT_GLOBALS.to_string()).map(|e| {
+            e.layer_over(&mut result, ENVIRONMENT_GLOBALS.to_string());
+        });
+        self.status.select.clone().map(|s| {
+            self.get(s.clone()).map(|e| e.layer_over(&mut result, s));
+        });
+        collection.clone().map(|c| {
+            c.envs
+                .layer_over(&mut result, c.folder.borrow().name.clone() + " Collection");
+        });
+        for ef in EnvFunction::iter() {
+            result.insert(
+                "$".to_string() + ef.to_string().as_str(),
+                EnvironmentItemValue {
+                    value: ef.to_string(),
+                    scope: "Global".to_string(),
+                    value_type: EnvironmentValueType::Function,
+                    ..Default::default()
+                },
+            );
+        }
+        result
     }
 
     pub fn load_all(&mut self, workspace: String) -> Result<(), Error> {
@@ -138,7 +71,15 @@ impl Environment {
         {
             if let Some(key_os) = key.file_name() {
                 if let Some(key_name) = key_os.to_str() {
-                    if let Some(environment_config) = self.persistence.load(key.clone()) {
+                    if let Some(mut environment_config) =
+                        self.persistence.load::<EnvironmentConfig>(key.clone())
+                    {
+                        if !environment_config.secrets().is_empty() {
+                            let secrets: Option<BTreeMap<String, String>> = self
+                                .persistence
+                                .load(Path::new("environment/secrets").join(key_name));
+                            environment_config.fill_secrets(&secrets.unwrap_or_default());
+                        }
                         self.data.insert(
                             Persistence::decode_with_file_name(key_name.to_string()),
                             environment_config,
@@ -161,12 +102,19 @@ impl Environment {
     pub fn get_data(&self) -> BTreeMap<String, EnvironmentConfig> {
         self.data.clone()
     }
+    /// Secret values are kept out of `environment/data` in `environment/secrets`, which the
+    /// git integration never commits.
     pub fn insert(&mut self, key: String, value: EnvironmentConfig) {
         self.data.insert(key.clone(), value.clone());
         self.persistence.save(
             Path::new("environment/data").to_path_buf(),
             key.clone(),
-            &value,
+            &value.without_secrets(),
+        );
+        self.persistence.save(
+            Path::new("environment/secrets").to_path_buf(),
+            key.clone(),
+            &value.secrets(),
         );
     }
 
@@ -174,6 +122,78 @@ impl Environment {
         self.data.remove(key.as_str());
         self.persistence
             .remove(Path::new("environment").to_path_buf(), key.clone());
+        self.persistence
+            .remove(Path::new("environment/secrets").to_path_buf(), key.clone());
+    }
+
+    /// Adds `key` to every environment that misses it.
+    pub fn add_key(&mut self, key: String, value: String) {
+        self.update_environments(|config| {
+            if !config.items.iter().any(|i| i.key == key) {
+                config.items.push(EnvironmentItem {
+                    enable: true,
+                    key: key.clone(),
+                    value: value.clone(),
+                    ..Default::default()
+                });
+            }
+        });
+    }
+
+    /// Fails without changes when an environment already has `new_key`.
+    pub fn rename_key(&mut self, key: String, new_key: String) -> anyhow::Result<()> {
+        if key == new_key {
+            return Ok(());
+        }
+        if let Some(name) = self
+            .data
+            .iter()
+            .filter(|(name, _)| name.as_str() != ENVIRONMENT_GLOBALS)
+            .find(|(_, config)| config.items.iter().any(|i| i.key == new_key))
+            .map(|(name, _)| name)
+        {
+            return Err(anyhow::anyhow!("`{}` already exists in {}", new_key, name));
+        }
+        self.update_environments(|config| {
+            for item in config.items.iter_mut().filter(|i| i.key == key) {
+                item.key = new_key.clone();
+            }
+        });
+        Ok(())
+    }
+
+    pub fn remove_key(&mut self, key: String) {
+        self.update_environments(|config| config.items.retain(|i| i.key != key));
+    }
+
+    fn update_environments(&mut self, mut update: impl FnMut(&mut EnvironmentConfig)) {
+        for (name, config) in self.get_data() {
+            if name == ENVIRONMENT_GLOBALS {
+                continue;
+            }
+            let mut new_config = config.clone();
+            update(&mut new_config);
+            if new_config != config {
+                self.insert(name, new_config);
+            }
+        }
+    }
+
+    /// Writes script changes of the active environment and globals, without an active
+    /// environment the `Environment` scope falls back to globals.
+    pub fn apply_changes(&mut self, changes: &Vec<EnvironmentChange>) {
+        for change in changes.iter() {
+            let key = match change.scope() {
+                EnvironmentScope::Environment => self
+                    .select()
+                    .unwrap_or(ENVIRONMENT_GLOBALS.to_string()),
+                EnvironmentScope::Globals => ENVIRONMENT_GLOBALS.to_string(),
+                EnvironmentScope::Collection => continue,
+            };
+            let mut config = self.get(key.clone()).unwrap_or_default();
+            config.apply(change);
+            self.insert(key, config);
+        }
     }
 }
 
@@ -181,6 +201,8 @@ impl Environment {
 pub enum EnvironmentValueType {
     String,
     Function,
+    Secret,
+    File,
 }
 
 impl Default for EnvironmentValueType {
@@ -189,6 +211,19 @@ impl Default for EnvironmentValueType {
     }
 }
 
+/// How the content of a `File` variable is substituted.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Display, EnumIter)]
+pub enum FileEncoding {
+    Text,
+    Base64,
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        FileEncoding::Text
+    }
+}
+
 #[derive(Default, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct EnvironmentStatus {
@@ -196,12 +231,34 @@ pub struct EnvironmentStatus {
 }
 
 pub const ENVIRONMENT_GLOBALS: &str = "__Globals__";
+pub const SECRET_MASK: &str = "******";
+/// Variables of an inner scope override the ones with the same key of an outer scope,
+/// folder variables are inherited by the subfolders and requests of the folder.
+pub const VARIABLE_PRECEDENCE: &str = "Request > Folder > Collection > Environment > Globals";
+
+/// Joins the relative paths of the file variables in `envs` with the workspace directory.
+pub fn resolve_file_paths(envs: &mut BTreeMap<String, EnvironmentItemValue>, workspace_dir: &Path) {
+    for value in envs.values_mut() {
+        if value.value_type == EnvironmentValueType::File && !value.value.is_empty() {
+            value.value = workspace_dir.join(&value.value).display().to_string();
+        }
+    }
+}
+
+/// The non-empty values of the secret variables in `envs`, to be masked in logs and reports.
+pub fn secret_values(envs: &BTreeMap<String, EnvironmentItemValue>) -> Vec<String> {
+    envs.values()
+        .filter(|v| v.value_type == EnvironmentValueType::Secret && !v.value.is_empty())
+        .map(|v| v.value.clone())
+        .collect()
+}
 
 #[derive(Default, Clone,Deserialize,Serialize,Debug)]
 pub struct EnvironmentItemValue {
     pub value: String,
     pub scope: String,
     pub value_type: EnvironmentValueType,
+    pub file_encoding: FileEncoding,
 }
 
 #[derive(Default, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -210,6 +267,312 @@ pub struct EnvironmentConfig {
     pub items: Vec<EnvironmentItem>,
 }
 
+#[derive(Clone, PartialEq, Eq, Debug, EnumIter, Display)]
+pub enum EnvironmentFileType {
+    Dotenv,
+    Json,
+    Postman,
+}
+
+impl EnvironmentFileType {
+    pub fn extension(&self) -> &str {
+        match self {
+            EnvironmentFileType::Dotenv => "env",
+            EnvironmentFileType::Json => "json",
+            EnvironmentFileType::Postman => "postman_environment.json",
+        }
+    }
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct PostmanEnvironment {
+    id: String,
+    name: String,
+    values: Vec<PostmanEnvironmentValue>,
+    #[serde(rename = "_postman_variable_scope")]
+    scope: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct PostmanEnvironmentValue {
+    key: String,
+    value: String,
+    #[serde(rename = "type")]
+    value_type: String,
+    enabled: bool,
+}
+
+impl Default for PostmanEnvironmentValue {
+    fn default() -> Self {
+        PostmanEnvironmentValue {
+            key: "".to_string(),
+            value: "".to_string(),
+            value_type: "default".to_string(),
+            enabled: true,
+        }
+    }
+}
+
+impl EnvironmentConfig {
+    /// Inserts the enabled items into `envs` with `scope`, replacing the outer scope values.
+    pub fn layer_over(&self, envs: &mut BTreeMap<String, EnvironmentItemValue>, scope: String) {
+        for item in self.items.iter().filter(|i| i.enable) {
+            envs.insert(
+                item.key.clone(),
+                EnvironmentItemValue {
+                    value: item.value.clone(),
+                    scope: scope.clone(),
+                    value_type: item.value_type.clone(),
+                    file_encoding: item.file_encoding.clone(),
+                },
+            );
+        }
+    }
+
+    /// Reads a `.env` file, a flat JSON object, a netpurr environment or a Postman
+    /// environment, the name is only known for Postman environments.
+    pub fn import(content: &str) -> anyhow::Result<(Option<String>, EnvironmentConfig)> {
+        let json = match serde_json::from_str::<serde_json::Value>(content) {
+            Ok(json) => json,
+            Err(_) => return Ok((None, Self::from_dotenv(content))),
+        };
+        if json.get("values").is_some() {
+            let postman: PostmanEnvironment = serde_json::from_value(json)?;
+            let items = postman
+                .values
+                .iter()
+                .map(|v| EnvironmentItem {
+                    enable: v.enabled,
+                    key: v.key.clone(),
+                    value: v.value.clone(),
+                    value_type: if v.value_type == "secret" {
+                        EnvironmentValueType::Secret
+                    } else {
+                        EnvironmentValueType::String
+                    },
+                    ..Default::default()
+                })
+                .collect();
+            return Ok((Some(postman.name), EnvironmentConfig { items }));
+        }
+        if json.get("items").is_some() {
+            return Ok((None, serde_json::from_value(json)?));
+        }
+        let object = json
+            .as_object()
+            .ok_or(anyhow::Error::msg("environment json must be an object"))?;
+        let items = object
+            .iter()
+            .map(|(key, value)| EnvironmentItem {
+                enable: true,
+                key: key.clone(),
+                value: match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                },
+                ..Default::default()
+            })
+            .collect();
+        Ok((None, EnvironmentConfig { items }))
+    }
+
+    pub fn export(&self, name: &str, file_type: &EnvironmentFileType) -> anyhow::Result<String> {
+        match file_type {
+            EnvironmentFileType::Dotenv => Ok(self.to_dotenv()),
+            EnvironmentFileType::Json => {
+                let object: serde_json::Map<String, serde_json::Value> = self
+                    .items
+                    .iter()
+                    .filter(|i| i.enable)
+                    .map(|i| (i.key.clone(), serde_json::Value::String(i.value.clone())))
+                    .collect();
+                Ok(serde_json::to_string_pretty(&object)?)
+            }
+            EnvironmentFileType::Postman => {
+                let postman = PostmanEnvironment {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    name: name.to_string(),
+                    values: self
+                        .items
+                        .iter()
+                        .map(|i| PostmanEnvironmentValue {
+                            key: i.key.clone(),
+                            value: i.value.clone(),
+                            value_type: match i.value_type {
+                                EnvironmentValueType::Secret => "secret".to_string(),
+                                _ => "default".to_string(),
+                            },
+                            enabled: i.enable,
+                        })
+                        .collect(),
+                    scope: "environment".to_string(),
+                };
+                Ok(serde_json::to_string_pretty(&postman)?)
+            }
+        }
+    }
+
+    fn from_dotenv(content: &str) -> EnvironmentConfig {
+        let mut items = vec![];
+        for line in content.lines().map(|l| l.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim();
+                let value = if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+                    unescape_dotenv(&value[1..value.len() - 1])
+                } else if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+                    value[1..value.len() - 1].to_string()
+                } else {
+                    value.to_string()
+                };
+                items.push(EnvironmentItem {
+                    enable: true,
+                    key: key.trim().to_string(),
+                    value,
+                    ..Default::default()
+                });
+            }
+        }
+        EnvironmentConfig { items }
+    }
+
+    // Disabled variables are written commented out.
+    fn to_dotenv(&self) -> String {
+        let mut lines = vec![];
+        for item in self.items.iter() {
+            let value = if item
+                .value
+                .contains(|c: char| c.is_whitespace() || c == '#' || c == '"' || c == '\'')
+            {
+                format!(
+                    "\"{}\"",
+                    item.value
+                        .replace('\\', "\\\\")
+                        .replace('"', "\\\"")
+                        .replace('\n', "\\n")
+                )
+            } else {
+                item.value.clone()
+            };
+            let line = format!("{}={}", item.key, value);
+            lines.push(if item.enable { line } else { format!("# {}", line) });
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// The workspace relative paths of the file variables.
+    pub fn files(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .filter(|i| i.value_type == EnvironmentValueType::File && !i.value.is_empty())
+            .map(|i| i.value.clone())
+            .collect()
+    }
+
+    pub fn secrets(&self) -> BTreeMap<String, String> {
+        self.items
+            .iter()
+            .filter(|i| i.value_type == EnvironmentValueType::Secret)
+            .map(|i| (i.key.clone(), i.value.clone()))
+            .collect()
+    }
+
+    pub fn without_secrets(&self) -> EnvironmentConfig {
+        let mut config = self.clone();
+        for item in config.items.iter_mut() {
+            if item.value_type == EnvironmentValueType::Secret {
+                item.value = "".to_string();
+            }
+        }
+        config
+    }
+
+    pub fn fill_secrets(&mut self, secrets: &BTreeMap<String, String>) {
+        for item in self.items.iter_mut() {
+            if item.value_type == EnvironmentValueType::Secret {
+                item.value = secrets.get(&item.key).cloned().unwrap_or_default();
+            }
+        }
+    }
+
+    pub fn apply(&mut self, change: &EnvironmentChange) {
+        match change {
+            EnvironmentChange::Set { key, value, .. } => {
+                match self.items.iter_mut().find(|i| i.key == *key) {
+                    None => self.items.push(EnvironmentItem {
+                        enable: true,
+                        key: key.clone(),
+                        value: value.clone(),
+                        ..Default::default()
+                    }),
+                    Some(item) => {
+                        item.enable = true;
+                        item.value = value.clone();
+                    }
+                }
+            }
+            EnvironmentChange::Unset { key, .. } => self.items.retain(|i| i.key != *key),
+            EnvironmentChange::Clear { .. } => self.items.clear(),
+        }
+    }
+}
+
+fn unescape_dotenv(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some(next) => result.push(next),
+            None => result.push(c),
+        }
+    }
+    result
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Display)]
+pub enum EnvironmentScope {
+    Environment,
+    Globals,
+    Collection,
+}
+
+/// A variable write made by a script, applied to the workspace after the run.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum EnvironmentChange {
+    Set {
+        scope: EnvironmentScope,
+        key: String,
+        value: String,
+    },
+    Unset {
+        scope: EnvironmentScope,
+        key: String,
+    },
+    Clear {
+        scope: EnvironmentScope,
+    },
+}
+
+impl EnvironmentChange {
+    pub fn scope(&self) -> EnvironmentScope {
+        match self {
+            EnvironmentChange::Set { scope, .. } => scope.clone(),
+            EnvironmentChange::Unset { scope, .. } => scope.clone(),
+            EnvironmentChange::Clear { scope } => scope.clone(),
+        }
+    }
+}
+
 #[derive(Default, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct EnvironmentItem {
@@ -218,4 +581,6 @@ pub struct EnvironmentItem {
     pub value: String,
     pub desc: String,
     pub value_type: EnvironmentValueType,
+    /// Only used by `File` items, whose value is a path relative to the workspace.
+    pub file_encoding: FileEncoding,
 }
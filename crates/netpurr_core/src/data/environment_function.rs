@@ -1,21 +1,133 @@
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use anyhow::Error;
+use chrono::{Duration, SecondsFormat, Utc};
+use rand::distributions::Alphanumeric;
+use rand::seq::SliceRandom;
 use rand::Rng;
 use strum_macros::{Display, EnumIter, EnumString};
 use uuid::Uuid;
 
 #[derive(EnumIter, EnumString, Display)]
+#[strum(ascii_case_insensitive)]
 pub enum EnvFunction {
     RandomInt,
     UUID,
     Timestamp,
+    IsoTimestamp,
+    RandomEmail,
+    RandomFirstName,
+    RandomLastName,
+    RandomFullName,
+    RandomPhone,
+    RandomIP,
+    RandomLorem,
+    FutureDate,
+    PastDate,
+    RandomAlphaNumeric,
+    RandomFrom,
+    Counter,
 }
 
-pub fn get_env_result(name: EnvFunction) -> String {
-    match name {
+const FIRST_NAMES: [&str; 16] = [
+    "James", "Mary", "John", "Linda", "Robert", "Emma", "Michael", "Olivia", "David", "Sophia",
+    "Daniel", "Mia", "Thomas", "Chloe", "Lucas", "Grace",
+];
+
+const LAST_NAMES: [&str; 16] = [
+    "Smith", "Johnson", "Williams", "Brown", "Jones", "Garcia", "Miller", "Davis", "Wilson",
+    "Moore", "Taylor", "Anderson", "Clark", "Lewis", "Walker", "Young",
+];
+
+const LOREM_WORDS: [&str; 24] = [
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do",
+    "eiusmod", "tempor", "incididunt", "ut", "labore", "et", "dolore", "magna", "aliqua", "enim",
+    "minim", "veniam", "quis", "nostrud",
+];
+
+static COUNTERS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+impl EnvFunction {
+    /// Parses a dynamic variable key like `$RandomInt` or `$randomInt(1,100)`.
+    pub fn parse_call(key: &str) -> Option<(EnvFunction, Vec<String>)> {
+        let call = key.trim().strip_prefix("$")?;
+        let (name, args) = match call.find('(') {
+            None => (call, vec![]),
+            Some(index) => (
+                &call[..index],
+                parse_args(call[index + 1..].strip_suffix(")")?),
+            ),
+        };
+        EnvFunction::from_str(name.trim()).ok().map(|f| (f, args))
+    }
+}
+
+// Splits `1, "a,b", 'c'` on the commas outside quotes and drops the quotes.
+fn parse_args(args: &str) -> Vec<String> {
+    let mut result = vec![];
+    let mut current = String::new();
+    let mut quote = None;
+    for c in args.chars() {
+        match quote {
+            Some(q) if q == c => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ',' => result.push(std::mem::take(&mut current).trim().to_string()),
+            None => current.push(c),
+        }
+    }
+    if !args.trim().is_empty() {
+        result.push(current.trim().to_string());
+    }
+    result
+}
+
+fn arg<T: FromStr>(args: &[String], index: usize, default: T) -> anyhow::Result<T> {
+    match args.get(index) {
+        None => Ok(default),
+        Some(value) => value
+            .parse::<T>()
+            .map_err(|_| Error::msg(format!("invalid argument `{}`", value))),
+    }
+}
+
+fn pick(list: &[&str]) -> String {
+    list.choose(&mut rand::thread_rng())
+        .unwrap_or(&"")
+        .to_string()
+}
+
+fn random_date(days: i64, future: bool) -> anyhow::Result<String> {
+    let invalid = || Error::msg(format!("invalid argument `{}`", days));
+    let now = Utc::now();
+    // the furthest date is checked first, so the random one in between can not overflow
+    let max_seconds = days.max(1).checked_mul(24 * 3600).ok_or_else(invalid)?;
+    let max_offset = Duration::from_std(std::time::Duration::from_secs(max_seconds as u64))
+        .map_err(|_| invalid())?;
+    let max_date = match future {
+        true => now.checked_add_signed(max_offset),
+        false => now.checked_sub_signed(max_offset),
+    };
+    max_date.ok_or_else(invalid)?;
+    let seconds = rand::thread_rng().gen_range(1..=max_seconds);
+    let offset = Duration::seconds(if future { seconds } else { -seconds });
+    Ok((now + offset).to_rfc3339_opts(SecondsFormat::Millis, true))
+}
+
+pub fn get_env_result(name: EnvFunction, args: &[String]) -> anyhow::Result<String> {
+    let mut rng = rand::thread_rng();
+    Ok(match name {
         EnvFunction::RandomInt => {
-            let i: i32 = rand::thread_rng().gen_range(0..i32::MAX);
-            i.to_string()
+            let min: i64 = arg(args, 0, 0)?;
+            let max: i64 = arg(args, 1, i32::MAX as i64)?;
+            if min > max {
+                return Err(Error::msg(format!("invalid range {}..{}", min, max)));
+            }
+            rng.gen_range(min..=max).to_string()
         }
         EnvFunction::UUID => Uuid::new_v4().to_string(),
         EnvFunction::Timestamp => {
@@ -26,5 +138,68 @@ pub fn get_env_result(name: EnvFunction) -> String {
             let timestamp_seconds = timestamp.as_secs();
             timestamp_seconds.to_string()
         }
+        EnvFunction::IsoTimestamp => Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        EnvFunction::RandomEmail => format!(
+            "{}.{}{}@example.com",
+            pick(&FIRST_NAMES).to_lowercase(),
+            pick(&LAST_NAMES).to_lowercase(),
+            rng.gen_range(1..1000)
+        ),
+        EnvFunction::RandomFirstName => pick(&FIRST_NAMES),
+        EnvFunction::RandomLastName => pick(&LAST_NAMES),
+        EnvFunction::RandomFullName => format!("{} {}", pick(&FIRST_NAMES), pick(&LAST_NAMES)),
+        EnvFunction::RandomPhone => format!(
+            "{}-{:03}-{:04}",
+            rng.gen_range(200..1000),
+            rng.gen_range(0..1000),
+            rng.gen_range(0..10000)
+        ),
+        EnvFunction::RandomIP => Ipv4Addr::from(rng.gen::<u32>()).to_string(),
+        EnvFunction::RandomLorem => {
+            let words: usize = arg(args, 0, 10)?;
+            (0..words)
+                .map(|_| pick(&LOREM_WORDS))
+                .collect::<Vec<String>>()
+                .join(" ")
+        }
+        EnvFunction::FutureDate => random_date(arg(args, 0, 365)?, true)?,
+        EnvFunction::PastDate => random_date(arg(args, 0, 365)?, false)?,
+        EnvFunction::RandomAlphaNumeric => {
+            let length: usize = arg(args, 0, 1)?;
+            (0..length)
+                .map(|_| rng.sample(Alphanumeric) as char)
+                .collect()
+        }
+        EnvFunction::RandomFrom => args
+            .choose(&mut rng)
+            .cloned()
+            .ok_or(Error::msg("RandomFrom needs at least one value"))?,
+        EnvFunction::Counter => {
+            let name = args.get(0).cloned().unwrap_or_default();
+            let mut counters = COUNTERS.lock().unwrap();
+            let counter = counters.entry(name).or_insert(0);
+            *counter += 1;
+            counter.to_string()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_date_rejects_out_of_range_days() {
+        for days in ["1", "365"] {
+            let args = vec![days.to_string()];
+            assert!(get_env_result(EnvFunction::FutureDate, &args).is_ok());
+            assert!(get_env_result(EnvFunction::PastDate, &args).is_ok());
+        }
+        for days in ["1000000000", "1000000000000", &i64::MAX.to_string()] {
+            let args = vec![days.to_string()];
+            let error = get_env_result(EnvFunction::FutureDate, &args).unwrap_err();
+            assert_eq!(error.to_string(), format!("invalid argument `{}`", days));
+            assert!(get_env_result(EnvFunction::PastDate, &args).is_err());
+        }
     }
 }
@@ -1,7 +1,10 @@
 use std::collections::BTreeMap;
-use std::path::Path;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use base64::Engine;
 use base64::engine::general_purpose;
@@ -16,7 +19,7 @@ use tokio_tungstenite::tungstenite::http::Uri;
 
 use crate::data::auth::Auth;
 use crate::data::collections::Testcase;
-use crate::data::environment::EnvironmentItemValue;
+use crate::data::environment::{EnvironmentConfig, EnvironmentItemValue};
 use crate::data::logger::Logger;
 
 #[derive(Default, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -32,6 +35,7 @@ pub struct HttpRecord {
     pub test_script: String,
     pub testcases: BTreeMap<String, Testcase>,
     pub operation_id: Option<String>,
+    pub envs: EnvironmentConfig,
 }
 
 impl HttpRecord {
@@ -83,6 +87,8 @@ pub struct Request {
     pub headers: Vec<Header>,
     pub body: HttpBody,
     pub auth: Auth,
+    pub protocol: HttpProtocol,
+    pub disable_decompression: bool,
 }
 
 impl IntoClientRequest for Request {
@@ -267,7 +273,7 @@ impl Request {
             .map(|h| h.compute_signature())
             .collect();
         format!(
-            "Schema:{} Method:{} BaseUrl:{} PathVariables:[{}] Params:[{}] Headers:[{}] Body:{} Auth:{}",
+            "Schema:{} Method:{} BaseUrl:{} PathVariables:[{}] Params:[{}] Headers:[{}] Body:{} Auth:{} Protocol:{} DisableDecompression:{}",
             self.schema,
             self.method,
             self.base_url,
@@ -275,7 +281,9 @@ impl Request {
             params.join(";"),
             headers.join(";"),
             self.body.compute_signature(),
-            self.auth.compute_signature()
+            self.auth.compute_signature(),
+            self.protocol,
+            self.disable_decompression
         )
     }
     pub fn clear_lock_with(&mut self) {
@@ -314,6 +322,47 @@ impl Request {
     }
 }
 
+/// What the pre-request scripts changed in the sent request besides headers and params. The
+/// editor only shows it, the request template keeps its own values.
+#[derive(Default, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ScriptRequestChanges {
+    pub method: Option<Method>,
+    pub url: Option<(RequestSchema, String)>,
+    pub path_variables: Option<Vec<PathVariables>>,
+    pub body: Option<(BodyType, String)>,
+    pub body_form_data: Vec<MultipartData>,
+    pub body_xxx_form: Vec<MultipartData>,
+    pub auth: Option<Auth>,
+}
+
+impl ScriptRequestChanges {
+    pub fn diff(before: &Request, after: &Request) -> Self {
+        let script_form = |form: &Vec<MultipartData>| {
+            form.iter()
+                .filter(|d| d.lock_with == LockWith::LockWithScript)
+                .cloned()
+                .collect()
+        };
+        ScriptRequestChanges {
+            method: (before.method != after.method).then(|| after.method.clone()),
+            url: (before.schema != after.schema || before.base_url != after.base_url)
+                .then(|| (after.schema.clone(), after.base_url.clone())),
+            path_variables: (before.path_variables != after.path_variables)
+                .then(|| after.path_variables.clone()),
+            body: (before.body.body_type != after.body.body_type
+                || before.body.body_str != after.body.body_str)
+                .then(|| (after.body.body_type.clone(), after.body.body_str.clone())),
+            body_form_data: script_form(&after.body.body_form_data),
+            body_xxx_form: script_form(&after.body.body_xxx_form),
+            auth: (before.auth != after.auth).then(|| after.auth.clone()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &ScriptRequestChanges::default()
+    }
+}
+
 impl HttpRecord {
     pub fn sync_everytime(
         &mut self,
@@ -371,6 +420,60 @@ impl Default for BodyRawType {
     }
 }
 
+#[derive(Clone, EnumIter, EnumString, Display, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ContentEncoding {
+    #[strum(serialize = "none")]
+    NONE,
+    #[strum(serialize = "gzip")]
+    GZIP,
+    #[strum(serialize = "deflate")]
+    DEFLATE,
+    #[strum(serialize = "br")]
+    BR,
+}
+
+impl Default for ContentEncoding {
+    fn default() -> Self {
+        ContentEncoding::NONE
+    }
+}
+
+impl ContentEncoding {
+    fn decoder<'a, R: Read + 'a>(&self, reader: R) -> Box<dyn Read + 'a> {
+        match self {
+            ContentEncoding::NONE => Box::new(reader),
+            ContentEncoding::GZIP => Box::new(flate2::read::GzDecoder::new(reader)),
+            ContentEncoding::DEFLATE => Box::new(flate2::read::ZlibDecoder::new(reader)),
+            ContentEncoding::BR => Box::new(brotli::Decompressor::new(reader, 4096)),
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut decoded = vec![];
+        self.decoder(bytes).read_to_end(&mut decoded)?;
+        Ok(decoded)
+    }
+
+    /// As much of `bytes` as decodes, for previews cut off inside the encoded stream.
+    pub fn decode_prefix(&self, bytes: &[u8]) -> Vec<u8> {
+        let mut decoder = self.decoder(bytes);
+        let mut decoded = vec![];
+        let mut buffer = [0; 8192];
+        while let Ok(n) = decoder.read(&mut buffer) {
+            if n == 0 {
+                break;
+            }
+            decoded.extend_from_slice(&buffer[..n]);
+        }
+        decoded
+    }
+
+    /// Decodes `reader` without keeping the result, for the size of streamed bodies.
+    pub fn decoded_size(&self, reader: impl Read) -> io::Result<u64> {
+        io::copy(&mut self.decoder(reader), &mut io::sink())
+    }
+}
+
 #[derive(Clone, EnumIter, EnumString, Display, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum BodyType {
     NONE,
@@ -505,6 +608,9 @@ pub struct Response {
     pub status: u16,
     pub status_text: String,
     pub elapsed_time: u128,
+    pub http_version: String,
+    /// Final url after redirects.
+    pub url: String,
     #[serde(skip)]
     pub logger: Logger,
 }
@@ -513,11 +619,18 @@ pub struct Response {
 #[serde(default)]
 pub struct HttpBody {
     pub base64: String,
+    /// Bytes received, the same whether the body was kept in memory or streamed to a file.
     pub size: usize,
+    /// `Content-Encoding` the kept response bytes still carry when decompression is disabled.
+    pub content_encoding: ContentEncoding,
+    /// Size with `content_encoding` removed, `None` when the body is not encoded or is corrupt.
+    pub decoded_size: Option<usize>,
+    pub response_file: String,
     pub body_str: String,
     pub body_file: String,
     pub body_type: BodyType,
     pub body_raw_type: BodyRawType,
+    pub body_encoding: ContentEncoding,
     pub body_form_data: Vec<MultipartData>,
     pub body_xxx_form: Vec<MultipartData>,
 }
@@ -537,11 +650,12 @@ impl HttpBody {
             .map(|b| b.compute_signature())
             .collect();
         format!(
-            "BodyStr:{} BodyFile:{} BodyType:{} BodyRawType:{} FormData:[{}] XXXForm:[{}]",
+            "BodyStr:{} BodyFile:{} BodyType:{} BodyRawType:{} BodyEncoding:{} FormData:[{}] XXXForm:[{}]",
             self.body_str,
             self.body_file,
             self.body_type,
             self.body_raw_type,
+            self.body_encoding,
             body_form_data.join(";"),
             body_xxx_form.join(";")
         )
@@ -551,30 +665,107 @@ impl HttpBody {
             .decode(&self.base64)
             .unwrap_or_default()
     }
+
+    /// The whole body, read back from `response_file` when it was streamed to disk.
+    pub fn to_full_vec(&self) -> Vec<u8> {
+        if self.is_truncated() {
+            fs::read(self.response_file.as_str()).unwrap_or_else(|_| self.to_vec())
+        } else {
+            self.to_vec()
+        }
+    }
     pub fn get_byte_size(&self) -> String {
-        if self.size > 1000000 {
-            return (self.size / 1000000).to_string() + " MB";
-        } else if self.size > 1000 {
-            return (self.size / 1000).to_string() + " KB";
+        Self::format_byte_size(self.size)
+    }
+
+    /// The whole body with its `content_encoding` removed.
+    pub fn to_decoded_vec(&self) -> anyhow::Result<Vec<u8>> {
+        self.content_encoding.decode(&self.to_full_vec())
+    }
+
+    pub fn format_byte_size(size: usize) -> String {
+        if size > 1000000 {
+            return (size / 1000000).to_string() + " MB";
+        } else if size > 1000 {
+            return (size / 1000).to_string() + " KB";
+        } else {
+            return size.to_string() + " B";
+        }
+    }
+
+    /// The body was streamed to `response_file` and `base64` only holds a preview.
+    pub fn is_truncated(&self) -> bool {
+        !self.response_file.is_empty()
+    }
+
+    pub fn save_to(&self, path: PathBuf) -> std::io::Result<()> {
+        if self.is_truncated() {
+            fs::copy(self.response_file.as_str(), path).map(|_| ())
         } else {
-            return self.size.to_string() + " B";
+            fs::write(path, self.to_vec())
         }
     }
 
+    pub fn new_with_file(preview: Vec<u8>, size: usize, response_file: PathBuf) -> Self {
+        let mut body = Self::new(preview);
+        body.size = size;
+        body.response_file = response_file.to_str().unwrap_or_default().to_string();
+        body
+    }
+
     pub fn new(bytes: Vec<u8>) -> Self {
         Self {
             base64: general_purpose::STANDARD.encode(&bytes).to_string(),
             size: bytes.len(),
+            content_encoding: Default::default(),
+            decoded_size: None,
+            response_file: "".to_string(),
             body_str: "".to_string(),
             body_file: "".to_string(),
             body_type: Default::default(),
             body_raw_type: Default::default(),
+            body_encoding: Default::default(),
             body_form_data: vec![],
             body_xxx_form: vec![],
         }
     }
 }
 
+#[derive(Default, Clone, Debug)]
+pub struct ResponseDownload {
+    pub dir: Option<PathBuf>,
+    state: Arc<RwLock<DownloadState>>,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct DownloadState {
+    pub received: usize,
+    pub total: Option<u64>,
+}
+
+impl ResponseDownload {
+    pub fn new(dir: PathBuf) -> Self {
+        ResponseDownload {
+            dir: Some(dir),
+            state: Default::default(),
+        }
+    }
+    pub fn get_dir(&self) -> PathBuf {
+        self.dir
+            .clone()
+            .unwrap_or(std::env::temp_dir().join(crate::APP_NAME).join("responses"))
+    }
+    pub fn get_state(&self) -> DownloadState {
+        self.state.read().unwrap().clone()
+    }
+    pub fn set_total(&self, total: Option<u64>) {
+        self.state.write().unwrap().total = total;
+    }
+    pub fn set_received(&self, received: usize) {
+        self.state.write().unwrap().received = received;
+    }
+}
+
 #[derive(Debug, Display, PartialEq, EnumString, EnumIter, Clone, Eq, Serialize, Deserialize)]
 pub enum Method {
     POST,
@@ -594,6 +785,21 @@ pub enum Method {
     VIEW,
 }
 
+#[derive(Debug, Display, PartialEq, EnumString, EnumIter, Clone, Eq, Hash, Serialize, Deserialize)]
+pub enum HttpProtocol {
+    Auto,
+    #[strum(serialize = "HTTP/1.1")]
+    Http1Only,
+    #[strum(serialize = "HTTP/2")]
+    Http2PriorKnowledge,
+}
+
+impl Default for HttpProtocol {
+    fn default() -> Self {
+        HttpProtocol::Auto
+    }
+}
+
 impl Default for Method {
     fn default() -> Self {
         Method::GET
@@ -0,0 +1,237 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::data::auth::Auth;
+use crate::data::collections::CollectionFolder;
+use crate::data::environment::EnvironmentItemValue;
+use crate::data::http::Request;
+use crate::data::record::Record;
+use crate::utils;
+
+/// A `{{var}}` template or a script variable read found in a collection.
+#[derive(Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariableUsage {
+    /// Path of the folder or request.
+    pub path: String,
+    /// Where in the folder or request, like `url`, `header Accept` or `test script`.
+    pub location: String,
+    pub key: String,
+    /// Why the variable can not be resolved with the environment, `None` when it can.
+    pub error: Option<String>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariableLintReport {
+    pub usages: Vec<VariableUsage>,
+    /// Variables written by scripts, they count as defined.
+    pub script_defined: BTreeSet<String>,
+}
+
+impl VariableLintReport {
+    pub fn unresolved(&self) -> Vec<VariableUsage> {
+        self.usages
+            .iter()
+            .filter(|u| u.error.is_some())
+            .cloned()
+            .collect()
+    }
+
+    /// Usage count of every referenced variable.
+    pub fn keys(&self) -> BTreeMap<String, usize> {
+        let mut keys = BTreeMap::default();
+        for usage in self.usages.iter() {
+            *keys.entry(usage.key.clone()).or_insert(0) += 1;
+        }
+        keys
+    }
+}
+
+/// Collects the variables referenced by the urls, headers, bodies, auth and scripts of
+/// `folder` and its children and checks them against `envs` and the variables of the
+/// subfolders and requests.
+pub fn lint_folder(
+    folder: Rc<RefCell<CollectionFolder>>,
+    envs: &BTreeMap<String, EnvironmentItemValue>,
+) -> VariableLintReport {
+    let mut linter = Linter {
+        envs: envs.clone(),
+        template_regex: Regex::new(r"\{\{.*?}}").unwrap(),
+        script_get_regex: Regex::new(
+            r#"(?:get_env|(?:environment|globals|variables|collection_variables|collectionVariables)\.get)\(\s*["'`]([^"'`]+)["'`]"#,
+        )
+        .unwrap(),
+        script_set_regex: Regex::new(
+            r#"(?:set_env|(?:environment|globals|variables|collection_variables|collectionVariables)\.set)\(\s*["'`]([^"'`]+)["'`]"#,
+        )
+        .unwrap(),
+        report: VariableLintReport::default(),
+    };
+    linter.collect_script_defined(&folder);
+    linter.lint_folder(&folder);
+    linter.report
+}
+
+struct Linter {
+    envs: BTreeMap<String, EnvironmentItemValue>,
+    template_regex: Regex,
+    script_get_regex: Regex,
+    script_set_regex: Regex,
+    report: VariableLintReport,
+}
+
+impl Linter {
+    fn collect_script_defined(&mut self, folder: &Rc<RefCell<CollectionFolder>>) {
+        let folder = folder.borrow();
+        let mut scripts = vec![folder.pre_request_script.clone(), folder.test_script.clone()];
+        for record in folder.requests.values() {
+            scripts.push(record.pre_request_script());
+            scripts.push(record.test_script());
+        }
+        for script in scripts.iter() {
+            for captures in self.script_set_regex.captures_iter(script) {
+                self.report.script_defined.insert(captures[1].to_string());
+            }
+        }
+        for child in folder.folders.values() {
+            self.collect_script_defined(child);
+        }
+    }
+
+    fn lint_folder(&mut self, folder: &Rc<RefCell<CollectionFolder>>) {
+        let folder = folder.borrow();
+        let path = folder.get_path();
+        self.lint_auth(&path, &folder.auth);
+        self.lint_script(&path, "pre-request script", &folder.pre_request_script);
+        self.lint_script(&path, "test script", &folder.test_script);
+        let outer_envs = self.envs.clone();
+        for (name, record) in folder.requests.iter() {
+            let record_path = format!("{}/{}", path, name);
+            record
+                .envs()
+                .layer_over(&mut self.envs, record.name() + " Request");
+            self.lint_record(&record_path, record);
+            self.envs = outer_envs.clone();
+        }
+        for child in folder.folders.values() {
+            let child_path = child.borrow().get_path();
+            child
+                .borrow()
+                .envs
+                .layer_over(&mut self.envs, child_path + " Folder");
+            self.lint_folder(child);
+            self.envs = outer_envs.clone();
+        }
+    }
+
+    fn lint_record(&mut self, path: &str, record: &Record) {
+        match record {
+            Record::Rest(rest) => self.lint_request(path, &rest.request),
+            Record::WebSocket(websocket) => {
+                self.lint_request(path, &websocket.http_record.request);
+                for (index, step) in websocket.scenario.iter().enumerate() {
+                    let location = format!("scenario step {}", index + 1);
+                    self.lint_text(path, &location, &step.content);
+                    self.lint_text(path, &location, &step.expect_value);
+                }
+            }
+        }
+        self.lint_script(path, "pre-request script", &record.pre_request_script());
+        self.lint_script(path, "test script", &record.test_script());
+    }
+
+    fn lint_request(&mut self, path: &str, request: &Request) {
+        self.lint_text(path, "url", &request.base_url);
+        for path_variable in request.path_variables.iter() {
+            let location = format!("path variable {}", path_variable.key);
+            self.lint_text(path, &location, &path_variable.value);
+        }
+        for param in request.params.iter().filter(|p| p.enable) {
+            let location = format!("param {}", param.key);
+            self.lint_text(path, &location, &param.key);
+            self.lint_text(path, &location, &param.value);
+        }
+        for header in request.headers.iter().filter(|h| h.enable) {
+            let location = format!("header {}", header.key);
+            self.lint_text(path, &location, &header.key);
+            self.lint_text(path, &location, &header.value);
+        }
+        self.lint_text(path, "body", &request.body.body_str);
+        for form in request
+            .body
+            .body_form_data
+            .iter()
+            .chain(request.body.body_xxx_form.iter())
+            .filter(|f| f.enable)
+        {
+            let location = format!("form {}", form.key);
+            self.lint_text(path, &location, &form.key);
+            self.lint_text(path, &location, &form.value);
+        }
+        self.lint_auth(path, &request.auth);
+    }
+
+    fn lint_auth(&mut self, path: &str, auth: &Auth) {
+        self.lint_text(path, "auth", &auth.bearer_token);
+        self.lint_text(path, "auth", &auth.basic_username);
+        self.lint_text(path, "auth", &auth.basic_password);
+    }
+
+    fn lint_text(&mut self, path: &str, location: &str, text: &str) {
+        let templates: Vec<String> = self
+            .template_regex
+            .find_iter(text)
+            .map(|find_match| find_match.as_str().to_string())
+            .collect();
+        for template in templates.iter() {
+            let key = template
+                .trim_start_matches("{{")
+                .trim_end_matches("}}")
+                .split(|c| c == '|' || c == '?')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            let error = if self.is_script_defined(&key) {
+                None
+            } else {
+                utils::check_variable(template.as_str(), &self.envs)
+                    .first()
+                    .map(|e| e.message.clone())
+            };
+            self.push(path, location, key, error);
+        }
+    }
+
+    fn lint_script(&mut self, path: &str, location: &str, script: &str) {
+        let keys: Vec<String> = self
+            .script_get_regex
+            .captures_iter(script)
+            .map(|captures| captures[1].to_string())
+            .collect();
+        for key in keys {
+            let error = if self.envs.contains_key(&key) || self.is_script_defined(&key) {
+                None
+            } else {
+                Some(format!("unknown variable `{}`", key))
+            };
+            self.push(path, location, key, error);
+        }
+    }
+
+    fn is_script_defined(&self, key: &str) -> bool {
+        !self.envs.contains_key(key) && self.report.script_defined.contains(key)
+    }
+
+    fn push(&mut self, path: &str, location: &str, key: String, error: Option<String>) {
+        self.report.usages.push(VariableUsage {
+            path: path.to_string(),
+            location: location.to_string(),
+            key,
+            error,
+        });
+    }
+}
@@ -1,6 +1,8 @@
 use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 
+use crate::data::environment::SECRET_MASK;
+
 #[derive(Default, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct Logger {
     pub logs: Vec<Log>,
@@ -32,6 +34,21 @@ impl Logger {
             scope,
         })
     }
+
+    /// Replaces every occurrence of the `secrets` in the messages.
+    pub fn mask(&mut self, secrets: &Vec<String>) {
+        for log in self.logs.iter_mut() {
+            log.msg = mask_secrets(log.msg.as_str(), secrets);
+        }
+    }
+}
+
+pub fn mask_secrets(text: &str, secrets: &Vec<String>) -> String {
+    let mut result = text.to_string();
+    for secret in secrets.iter().filter(|s| !s.is_empty()) {
+        result = result.replace(secret.as_str(), SECRET_MASK);
+    }
+    result
 }
 
 #[derive(Default, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -6,8 +6,10 @@ pub mod environment;
 pub mod environment_function;
 pub mod history;
 pub mod http;
+pub mod lint;
 pub mod logger;
 pub mod record;
 pub mod test;
 pub mod websocket;
 pub mod workspace_data;
+pub mod workspace_settings;
@@ -3,6 +3,7 @@ use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 
 use crate::data::collections::Testcase;
+use crate::data::environment::EnvironmentConfig;
 use crate::data::http::HttpRecord;
 use crate::data::websocket::WebSocketRecord;
 
@@ -50,6 +51,12 @@ impl Record {
             Record::WebSocket(websocket) => BTreeMap::new(),
         }
     }
+    pub fn envs(&self) -> EnvironmentConfig {
+        self.must_get_rest().envs.clone()
+    }
+    pub fn set_envs(&mut self, envs: EnvironmentConfig) {
+        self.must_get_mut_rest().envs = envs;
+    }
     pub fn must_get_rest(&self) -> &HttpRecord {
         match self {
             Record::Rest(rest) => rest,
@@ -71,7 +71,8 @@ pub enum TestStatus {
     RUNNING,
     PASS,
     FAIL,
-    SKIP
+    SKIP,
+    CANCELLED,
 }
 
 impl Default for TestStatus {
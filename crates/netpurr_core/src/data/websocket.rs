@@ -18,6 +18,7 @@ pub struct WebSocketRecord {
     pub select_message_type: MessageType,
     pub retain_content: String,
     pub history_send_messages: Vec<(MessageType, String)>,
+    pub scenario: Vec<WebSocketScenarioStep>,
     #[serde(skip)]
     pub session: Option<WebSocketSession>,
 }
@@ -38,17 +39,21 @@ impl Default for WebSocketRecord {
                     headers: vec![],
                     body: Default::default(),
                     auth: Default::default(),
+                    protocol: Default::default(),
+                    disable_decompression: false,
                 },
                 response: Default::default(),
                 status: Default::default(),
                 pre_request_script: "".to_string(),
                 test_script: "".to_string(),
                 testcases: Default::default(),
+                envs: Default::default(),
                 operation_id: None,
             },
             select_message_type: Default::default(),
             retain_content: "".to_string(),
             history_send_messages: vec![],
+            scenario: vec![],
             session: None,
         }
     }
@@ -56,10 +61,16 @@ impl Default for WebSocketRecord {
 
 impl WebSocketRecord {
     pub fn compute_signature(&self) -> String {
+        let scenario: Vec<String> = self
+            .scenario
+            .iter()
+            .map(|s| s.compute_signature())
+            .collect();
         format!(
-            "HttpRecord:{} History:{}",
+            "HttpRecord:{} History:{} Scenario:[{}]",
             self.http_record.compute_signature(),
-            self.history_send_messages.len()
+            self.history_send_messages.len(),
+            scenario.join(";")
         )
     }
     pub fn connected(&self) -> bool {
@@ -173,7 +184,13 @@ impl WebSocketSession {
         &self,
         response: tokio_tungstenite::tungstenite::handshake::client::Response,
     ) {
-        let http_response = Response {
+        self.state.lock().unwrap().response = Self::build_handshake_response(response);
+    }
+
+    pub fn build_handshake_response(
+        response: tokio_tungstenite::tungstenite::handshake::client::Response,
+    ) -> Response {
+        Response {
             request: Default::default(),
             body: Arc::new(Default::default()),
             headers: response
@@ -190,9 +207,10 @@ impl WebSocketSession {
             status: response.status().as_u16(),
             status_text: "".to_string(),
             elapsed_time: 0,
+            http_version: format!("{:?}", response.version()),
+            url: "".to_string(),
             logger: Default::default(),
-        };
-        self.state.lock().unwrap().response = http_response;
+        }
     }
 
     pub fn next_event(&self) -> Option<WebSocketStatus> {
@@ -219,3 +237,91 @@ impl Default for MessageType {
         MessageType::Text
     }
 }
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebSocketScenarioStep {
+    pub step_type: WebSocketStepType,
+    pub message_type: MessageType,
+    /// Message to send for `Send`, regex or JSONPath expression for `Expect`.
+    pub content: String,
+    pub match_type: WebSocketMatchType,
+    /// Expected value at the JSONPath, any value matches when empty.
+    pub expect_value: String,
+    /// Milliseconds to wait for a matching message.
+    pub timeout: u64,
+}
+
+impl Default for WebSocketScenarioStep {
+    fn default() -> Self {
+        WebSocketScenarioStep {
+            step_type: Default::default(),
+            message_type: Default::default(),
+            content: "".to_string(),
+            match_type: Default::default(),
+            expect_value: "".to_string(),
+            timeout: 5000,
+        }
+    }
+}
+
+impl WebSocketScenarioStep {
+    pub fn compute_signature(&self) -> String {
+        format!(
+            "Type:{} MessageType:{} Content:{} Match:{} Expect:{} Timeout:{}",
+            self.step_type,
+            self.message_type,
+            self.content,
+            self.match_type,
+            self.expect_value,
+            self.timeout
+        )
+    }
+
+    pub fn get_name(&self, index: usize) -> String {
+        match self.step_type {
+            WebSocketStepType::Send => format!("Step {}: send `{}`", index + 1, self.content),
+            WebSocketStepType::Expect => match self.match_type {
+                WebSocketMatchType::Regex => {
+                    format!("Step {}: expect match `{}`", index + 1, self.content)
+                }
+                WebSocketMatchType::JsonPath => {
+                    if self.expect_value.is_empty() {
+                        format!("Step {}: expect `{}` exists", index + 1, self.content)
+                    } else {
+                        format!(
+                            "Step {}: expect `{}` equal `{}`",
+                            index + 1,
+                            self.content,
+                            self.expect_value
+                        )
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, EnumIter, EnumString, Display)]
+pub enum WebSocketStepType {
+    Send,
+    Expect,
+}
+
+impl Default for WebSocketStepType {
+    fn default() -> Self {
+        WebSocketStepType::Send
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, EnumIter, EnumString, Display)]
+pub enum WebSocketMatchType {
+    Regex,
+    JsonPath,
+}
+
+impl Default for WebSocketMatchType {
+    fn default() -> Self {
+        WebSocketMatchType::Regex
+    }
+}
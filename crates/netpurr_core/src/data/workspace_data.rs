@@ -1,6 +1,10 @@
 use std::cell::RefCell;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
 
 use chrono::NaiveDate;
 use log::error;
@@ -11,23 +15,33 @@ use crate::data::auth::{Auth, AuthType};
 use crate::data::central_request_data::{CentralRequestDataList, CentralRequestItem};
 use crate::data::collections::{Collection, CollectionFolder, Collections};
 use crate::data::cookies_manager::{Cookie, CookiesManager};
-use crate::data::environment::{Environment, EnvironmentConfig, EnvironmentItemValue};
+use crate::data::environment::{
+    resolve_file_paths, Environment, EnvironmentChange, EnvironmentConfig, EnvironmentItemValue,
+    EnvironmentScope,
+};
 use crate::data::history::{DateGroupHistoryList, HistoryDataList};
 use crate::data::record::Record;
+use crate::data::workspace_settings::WorkspaceSettings;
+use crate::persistence::{Persistence, PersistenceItem};
 use crate::runner::TestRunResult;
-use crate::script::{ScriptScope, ScriptTree};
+use crate::script::{ScriptError, ScriptLimits, ScriptScope, ScriptTree};
 use crate::utils;
 
+static SESSION_START: OnceLock<SystemTime> = OnceLock::new();
+
 #[derive(Default, Clone, Debug)]
 pub struct WorkspaceData {
     pub workspace_name: String,
     pub editor_model: EditorModel,
     pub selected_test_item: Option<TestItem>,
     pub selected_test_run_result: Option<TestRunResult>,
+    /// Last script failure, the script editors highlight its line.
+    pub script_error: Option<ScriptError>,
     cookies_manager: RefCell<CookiesManager>,
     central_request_data_list: RefCell<CentralRequestDataList>,
     history_data_list: RefCell<HistoryDataList>,
     environment: RefCell<Environment>,
+    workspace_settings: RefCell<WorkspaceSettings>,
     collections: RefCell<Collections>,
 }
 
@@ -184,7 +198,9 @@ impl WorkspaceData {
         &self,
         collection: Option<Collection>,
     ) -> BTreeMap<String, EnvironmentItemValue> {
-        self.environment.borrow().get_variable_hash_map(collection)
+        let mut envs = self.environment.borrow().get_variable_hash_map(collection);
+        resolve_file_paths(&mut envs, &self.get_workspace_dir());
+        envs
     }
     pub fn get_env_select(&self) -> Option<String> {
         self.environment.borrow().select()
@@ -208,6 +224,39 @@ impl WorkspaceData {
     pub fn remove_env(&self, key: String) {
         self.environment.borrow_mut().remove(key)
     }
+
+    pub fn add_env_key(&self, key: String, value: String) {
+        self.environment.borrow_mut().add_key(key, value)
+    }
+
+    pub fn rename_env_key(&self, key: String, new_key: String) -> anyhow::Result<()> {
+        self.environment.borrow_mut().rename_key(key, new_key)
+    }
+
+    pub fn remove_env_key(&self, key: String) {
+        self.environment.borrow_mut().remove_key(key)
+    }
+
+    pub fn apply_env_changes(&self, collection_path: Option<String>, changes: Vec<EnvironmentChange>) {
+        if changes.is_empty() {
+            return;
+        }
+        self.environment.borrow_mut().apply_changes(&changes);
+        if let Some(mut collection) = self.get_collection(collection_path) {
+            let collection_changes: Vec<&EnvironmentChange> = changes
+                .iter()
+                .filter(|c| c.scope() == EnvironmentScope::Collection)
+                .collect();
+            if collection_changes.is_empty() {
+                return;
+            }
+            for change in collection_changes {
+                collection.envs.apply(change);
+            }
+            let name = collection.folder.borrow().name.clone();
+            self.update_collection_info(name, collection);
+        }
+    }
 }
 
 // history
@@ -320,9 +369,22 @@ impl WorkspaceData {
         }
         self.must_get_crt(id)
     }
+    /// The variables of the collection and of every folder along `path`.
+    pub fn get_path_envs(&self, path: Option<String>) -> BTreeMap<String, EnvironmentItemValue> {
+        let mut envs = self.get_build_envs(self.get_collection(path.clone()));
+        path.map(|p| self.collections.borrow().layer_path_envs(p, &mut envs));
+        resolve_file_paths(&mut envs, &self.get_workspace_dir());
+        envs
+    }
+
     pub fn get_crt_envs(&self, id: String) -> BTreeMap<String, EnvironmentItemValue> {
         let crt = self.must_get_crt(id);
-        self.get_build_envs(self.get_collection(crt.collection_path.clone()))
+        let mut envs = self.get_path_envs(crt.collection_path.clone());
+        crt.record
+            .envs()
+            .layer_over(&mut envs, crt.record.name() + " Request");
+        resolve_file_paths(&mut envs, &self.get_workspace_dir());
+        envs
     }
 
     pub fn get_path_parent_auth(&self, path: String) -> Auth {
@@ -370,6 +432,14 @@ impl WorkspaceData {
 
     pub fn get_script_tree(&self, collection_path: String) -> ScriptTree {
         let mut script_tree = ScriptTree::default();
+        script_tree.openapi = self
+            .get_collection(Some(collection_path.clone()))
+            .and_then(|c| c.openapi)
+            .map(Arc::new);
+        script_tree.lib_dir = Some(self.get_script_lib_dir());
+        script_tree.workspace_dir = Some(self.get_workspace_dir());
+        script_tree.script_limits = self.get_script_limits();
+        script_tree.environment = self.get_env_select();
         if let (_, Some(folder)) = self.get_folder_with_path(collection_path) {
             self._get_script_tree(folder.clone(), &mut script_tree);
         }
@@ -494,8 +564,96 @@ impl WorkspaceData {
 }
 
 impl WorkspaceData {
+    pub fn get_response_cache_dir(&self) -> PathBuf {
+        let mut persistence = Persistence::default();
+        persistence.set_workspace(self.workspace_name.clone());
+        persistence.get_workspace_dir().join("responses")
+    }
+    pub fn get_script_limits(&self) -> ScriptLimits {
+        self.workspace_settings.borrow().get_script_limits()
+    }
+    pub fn set_script_limits(&self, script_limits: ScriptLimits) {
+        self.workspace_settings
+            .borrow_mut()
+            .set_script_limits(script_limits)
+    }
+    pub fn get_workspace_dir(&self) -> PathBuf {
+        let mut persistence = Persistence::default();
+        persistence.set_workspace(self.workspace_name.clone());
+        persistence.get_workspace_dir()
+    }
+    /// The workspace relative paths of the file variables of the environments, collections,
+    /// folders and requests.
+    pub fn get_env_files(&self) -> BTreeSet<String> {
+        let mut files = BTreeSet::new();
+        for config in self.get_env_configs().values() {
+            files.extend(config.files());
+        }
+        for collection in self.get_collections().values() {
+            files.extend(collection.envs.files());
+            collection.folder.borrow().collect_env_files(&mut files);
+        }
+        files
+    }
+    pub fn get_script_lib_dir(&self) -> PathBuf {
+        let mut persistence = Persistence::default();
+        persistence.set_workspace(self.workspace_name.clone());
+        persistence.get_workspace_dir().join("scripts")
+    }
+    /// Library scripts as the names `lib:` imports use, only the top of `scripts/` is listed.
+    pub fn get_script_libs(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(self.get_script_lib_dir()) else {
+            return vec![];
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter(|e| e.path().is_file())
+            .filter_map(|e| e.file_name().to_str().map(|name| name.to_string()))
+            .filter(|name| name.ends_with(".js"))
+            .collect();
+        names.sort();
+        names
+    }
+    pub fn read_script_lib(&self, name: &str) -> String {
+        fs::read_to_string(self.get_script_lib_dir().join(name)).unwrap_or_default()
+    }
+    pub fn save_script_lib(&self, name: &str, script: &str) -> anyhow::Result<()> {
+        if name.is_empty()
+            || !name.ends_with(".js")
+            || name.contains(['/', '\\'])
+            || name.starts_with('.')
+        {
+            return Err(anyhow::anyhow!(
+                "`{}` must be a file name ending with .js",
+                name
+            ));
+        }
+        fs::create_dir_all(self.get_script_lib_dir())?;
+        fs::write(self.get_script_lib_dir().join(name), script)?;
+        Ok(())
+    }
+    pub fn remove_script_lib(&self, name: &str) -> anyhow::Result<()> {
+        Ok(fs::remove_file(self.get_script_lib_dir().join(name))?)
+    }
+    /// The line of `script` in `test_item` the last script error points at. Test runs prefix
+    /// record scopes with testcase names, so records match on the scope suffix.
+    pub fn script_error_line(&self, test_item: &TestItem, script: &str) -> Option<usize> {
+        let script_error = self.script_error.as_ref()?;
+        let scope_matches = match test_item {
+            TestItem::Folder(_, folder) => script_error.scope == folder.borrow().get_path(),
+            TestItem::Record(_, _, record_name) => script_error
+                .scope
+                .ends_with(format!("/{}", record_name).as_str()),
+        };
+        if !scope_matches {
+            return None;
+        }
+        script_error.line_in(script)
+    }
     pub fn load_all(&mut self, workspace: String) {
         self.workspace_name = workspace.clone();
+        self.script_error = None;
+        SESSION_START.get_or_init(SystemTime::now);
         self.editor_model = EditorModel::Request;
         self.selected_test_item = None;
         self.central_request_data_list
@@ -505,16 +663,52 @@ impl WorkspaceData {
             .borrow_mut()
             .load_all(workspace.clone());
         self.environment.borrow_mut().load_all(workspace.clone());
+        self.workspace_settings
+            .borrow_mut()
+            .load_all(workspace.clone());
         self.collections.borrow_mut().load_all(workspace.clone());
+        self.clean_response_cache();
         self.cookies_manager
             .borrow_mut()
             .load_all(workspace.clone())
     }
+
+    /// Removes the streamed response bodies of earlier sessions that no open request or
+    /// history item references, the bodies streamed in this session may still be shown.
+    fn clean_response_cache(&self) {
+        let session_start = *SESSION_START.get_or_init(SystemTime::now);
+        let mut referenced = HashSet::new();
+        for crt in self.central_request_data_list.borrow().data_map.values() {
+            referenced.insert(crt.record.must_get_rest().response.body.response_file.clone());
+        }
+        for group in self.history_data_list.borrow().get_group().values() {
+            for history in group.history_list.iter() {
+                let response = &history.record.must_get_rest().response;
+                referenced.insert(response.body.response_file.clone());
+            }
+        }
+        let Ok(entries) = fs::read_dir(self.get_response_cache_dir()) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let stale = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .map(|modified| modified < session_start)
+                .unwrap_or(false);
+            if stale && !referenced.contains(&entry.path().display().to_string()) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
     pub fn reload_data(&mut self, workspace: String) {
         self.history_data_list
             .borrow_mut()
             .load_all(workspace.clone());
         self.environment.borrow_mut().load_all(workspace.clone());
+        self.workspace_settings
+            .borrow_mut()
+            .load_all(workspace.clone());
         self.collections.borrow_mut().load_all(workspace.clone());
         self.cookies_manager
             .borrow_mut()
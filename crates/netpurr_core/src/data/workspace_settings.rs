@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::persistence::{get_persistence_path, Persistence, PersistenceItem};
+use crate::script::ScriptLimits;
+
+/// Settings shared by everyone using the workspace, saved as `settings/workspace.yaml`.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct WorkspaceSettings {
+    persistence: Persistence,
+    data: WorkspaceSettingsData,
+}
+
+#[derive(Default, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceSettingsData {
+    pub script_limits: ScriptLimits,
+}
+
+impl WorkspaceSettings {
+    pub fn load_all(&mut self, workspace: String) {
+        self.persistence.set_workspace(workspace);
+        self.data = self
+            .persistence
+            .load(Path::new(get_persistence_path("settings/workspace").as_str()).to_path_buf())
+            .unwrap_or_default();
+    }
+    pub fn get_script_limits(&self) -> ScriptLimits {
+        self.data.script_limits.clone()
+    }
+    pub fn set_script_limits(&mut self, script_limits: ScriptLimits) {
+        self.data.script_limits = script_limits;
+        self.persistence.save(
+            Path::new("settings").to_path_buf(),
+            "workspace".to_string(),
+            &self.data,
+        );
+    }
+}
@@ -19,22 +19,30 @@ use log::{info, log};
 use poll_promise::Promise;
 use rayon::{Scope, ThreadPool, ThreadPoolBuilder};
 use reqwest::Client;
+use openapiv3::OpenAPI;
 use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
 
 use reqwest_cookie_store::CookieStoreMutex;
 use rest::RestSender;
+use tokio_util::sync::CancellationToken;
 
 use crate::data::collections::{CollectionFolder, CollectionFolderOnlyRead, Testcase};
-use crate::data::environment::EnvironmentItemValue;
-use crate::data::http::{Request, Response};
-use crate::data::logger::Logger;
+use crate::data::environment::{
+    resolve_file_paths, secret_values, EnvironmentChange, EnvironmentItemValue,
+};
+use crate::data::http::{
+    HttpProtocol, Request, Response, ResponseDownload, ScriptRequestChanges,
+};
+use crate::data::logger::{mask_secrets, Logger};
 use crate::data::record::Record;
 use crate::data::test::{TestResult, TestStatus};
-use crate::data::websocket::WebSocketSession;
+use crate::data::websocket::{WebSocketScenarioStep, WebSocketSession};
 use crate::runner;
 use crate::runner::html_report::{HtmlReport, HtmlReportTestResult};
 use crate::runner::websocket::WebSocketSender;
-use crate::script::{Context, JsResponse, ScriptRuntime, ScriptScope, ScriptTree, SharedMap, SkipError};
+use crate::script::{Context, JsResponse, ScriptError, ScriptLimits, ScriptRuntime, ScriptScope, ScriptTree, SharedMap, SkipError};
+use crate::utils::replace_variable_with_logger;
 
 mod rest;
 pub mod test;
@@ -44,7 +52,50 @@ mod html_report;
 #[derive(Clone)]
 pub struct Runner {
     script_runtime: ScriptRuntime,
-    client: Client,
+    clients: HttpClients,
+}
+
+/// One client per protocol preference and decompression setting, all sharing the same cookie store.
+#[derive(Clone, Default)]
+pub struct HttpClients {
+    clients: HashMap<(HttpProtocol, bool), Client>,
+}
+
+impl HttpClients {
+    pub fn new(cookie_store: Option<Arc<CookieStoreMutex>>) -> Self {
+        let mut clients = HashMap::new();
+        for protocol in HttpProtocol::iter() {
+            for disable_decompression in [false, true] {
+                let mut builder = Client::builder()
+                    .danger_accept_invalid_certs(true)
+                    .trust_dns(true)
+                    .tcp_nodelay(true)
+                    .connect_timeout(Duration::from_secs(60));
+                if let Some(cookie_store) = &cookie_store {
+                    builder = builder.cookie_provider(cookie_store.clone());
+                }
+                builder = match protocol {
+                    HttpProtocol::Auto => builder,
+                    HttpProtocol::Http1Only => builder.http1_only(),
+                    HttpProtocol::Http2PriorKnowledge => builder.http2_prior_knowledge(),
+                };
+                if disable_decompression {
+                    builder = builder.no_gzip().no_brotli().no_deflate();
+                }
+                clients.insert(
+                    (protocol.clone(), disable_decompression),
+                    builder.build().unwrap_or_default(),
+                );
+            }
+        }
+        HttpClients { clients }
+    }
+    pub fn get(&self, request: &Request) -> Client {
+        self.clients
+            .get(&(request.protocol.clone(), request.disable_decompression))
+            .cloned()
+            .unwrap_or_default()
+    }
 }
 #[derive(Clone, Debug)]
 pub struct RunRequestInfo {
@@ -56,6 +107,25 @@ pub struct RunRequestInfo {
     pub pre_request_scripts: Vec<ScriptScope>,
     pub test_scripts: Vec<ScriptScope>,
     pub testcase: Testcase,
+    pub websocket_scenario: Option<Vec<WebSocketScenarioStep>>,
+    pub download: ResponseDownload,
+    pub cancel_token: CancellationToken,
+    pub openapi: Option<Arc<OpenAPI>>,
+    pub operation_id: Option<String>,
+    pub lib_dir: Option<PathBuf>,
+    /// File variables of folders and requests are resolved relative to it.
+    pub workspace_dir: Option<PathBuf>,
+    pub script_limits: ScriptLimits,
+    /// Selected environment name, scripts resolve the `Environment` scope to it.
+    pub environment: Option<String>,
+}
+impl RunRequestInfo {
+    fn collection_name(&self) -> Option<String> {
+        self.collection_path
+            .as_ref()
+            .and_then(|path| path.split('/').next())
+            .map(|name| name.to_string())
+    }
 }
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct TestRunResult {
@@ -65,6 +135,8 @@ pub struct TestRunResult {
     pub collection_path: Option<String>,
     pub request_name: String,
     pub testcase: Testcase,
+    pub env_changes: Vec<EnvironmentChange>,
+    pub script_changes: ScriptRequestChanges,
 }
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct TestRunError {
@@ -74,26 +146,26 @@ pub struct TestRunError {
     pub request_name: String,
     pub testcase: Testcase,
     pub error: String,
+    /// Set when a pre-request or test script threw, points at the failing scope and line.
+    pub script_error: Option<ScriptError>,
+    /// Variable writes of the scripts that finished before the failure, applied like on success.
+    pub env_changes: Vec<EnvironmentChange>,
 }
 impl Runner {
     pub fn new(cookie_store: Arc<CookieStoreMutex>) -> Self {
         Runner {
             script_runtime: Default::default(),
-            client: Client::builder()
-                .danger_accept_invalid_certs(true)
-                .cookie_provider(cookie_store)
-                .trust_dns(true)
-                .tcp_nodelay(true)
-                .timeout(Duration::from_secs(60))
-                .build()
-                .unwrap_or_default(),
+            clients: HttpClients::new(Some(cookie_store)),
         }
     }
     pub fn run_script(
         &self,
         scripts: Vec<ScriptScope>,
-        context: Context,
+        mut context: Context,
     ) -> Promise<anyhow::Result<Context>> {
+        if context.client.is_none() {
+            context.client = Some(self.clients.get(&context.request));
+        }
         self.script_runtime.run_block(scripts, context)
     }
 
@@ -105,155 +177,380 @@ impl Runner {
     }
     pub async fn send_rest_with_script_async(
         run_request_info: RunRequestInfo,
-        client: Client,
+        clients: HttpClients,
+        secrets: &mut Vec<String>,
     ) -> Result<TestRunResult, TestRunError> {
-        info!("start send_rest_with_script_async:{:?}",run_request_info);
-        let shared_map = run_request_info.shared_map;
+        // the request and envs hold secrets, the log file only gets the name
+        info!(
+            "start send_rest_with_script_async: {:?} {}",
+            run_request_info.collection_path, run_request_info.request_name
+        );
         let mut logger = Logger::default();
-        let mut default_context = Context {
+        let pre_request_context =
+            match Self::run_pre_request_scripts(&run_request_info, &clients, &mut logger, secrets)
+                .await
+            {
+                Ok(context) => context,
+                Err(result) => return result,
+            };
+        logger.add_info("System".to_string(),format!("Envs: \n{}",serde_yaml::to_string(&pre_request_context.envs).unwrap()));
+        let build_request = RestSender::build_request(
+            pre_request_context.request.clone(),
+            pre_request_context.envs.clone(),
+            &mut logger,
+        );
+        logger.add_info(
+            "Fetch".to_string(),
+            format!("start fetch request: \n{}", serde_yaml::to_string(&build_request).unwrap()),
+        );
+        let client = clients.get(&build_request);
+        match RestSender::reqwest_async_send(
+            build_request,
+            client,
+            run_request_info.download.clone(),
+            run_request_info.cancel_token.clone(),
+        ).await {
+            Ok((after_request, mut response)) => {
+                logger.add_info("Fetch".to_string(), "get response".to_string());
+                response.logger = logger;
+                Self::run_test_scripts(
+                    &run_request_info,
+                    pre_request_context,
+                    after_request,
+                    response,
+                    TestResult::default(),
+                    secrets,
+                )
+                .await
+            }
+            Err(e) => Err(Self::run_error(
+                &run_request_info,
+                run_request_info.request.clone(),
+                None,
+                &e,
+                pre_request_context.env_changes,
+            )),
+        }
+    }
+    fn script_context(run_request_info: &RunRequestInfo, clients: &HttpClients) -> Context {
+        Context {
             scope_name: "".to_string(),
             request: run_request_info.request.clone(),
             envs: run_request_info.envs.clone(),
             testcase: run_request_info.testcase.clone(),
-            shared_map,
+            shared_map: run_request_info.shared_map.clone(),
+            openapi: run_request_info.openapi.clone(),
+            operation_id: run_request_info.operation_id.clone(),
+            client: Some(clients.get(&run_request_info.request)),
+            lib_dir: run_request_info.lib_dir.clone(),
+            script_limits: run_request_info.script_limits.clone(),
+            environment: run_request_info.environment.clone(),
+            collection: run_request_info.collection_name(),
             ..Default::default()
-        };
-        default_context
-            .logger
-            .add_info("System".to_string(), format!("Testcase: \n{}",serde_yaml::to_string(&default_context.testcase).unwrap()));
-        default_context
-            .logger
-            .add_info("System".to_string(), format!("Envs: \n{}",serde_yaml::to_string(&default_context.envs).unwrap()));
-        let mut pre_request_context_result = Ok(default_context.clone());
+        }
+    }
+    /// Runs the pre-request scripts and moves their logs to `logger`. A skip or a script error
+    /// ends the run, it is returned as the final result.
+    async fn run_pre_request_scripts(
+        run_request_info: &RunRequestInfo,
+        clients: &HttpClients,
+        logger: &mut Logger,
+        secrets: &mut Vec<String>,
+    ) -> Result<Context, Result<TestRunResult, TestRunError>> {
+        let mut context = Self::script_context(run_request_info, clients);
+        logger.add_info("System".to_string(), format!("Testcase: \n{}",serde_yaml::to_string(&context.testcase).unwrap()));
+        logger.add_info("System".to_string(), format!("Envs: \n{}",serde_yaml::to_string(&context.envs).unwrap()));
         if run_request_info.pre_request_scripts.len() > 0 {
-            default_context
+            logger.add_info("System".to_string(), "Run pre-request-scripts".to_string());
+            context = match ScriptRuntime::run_async(
+                run_request_info.pre_request_scripts.clone(),
+                context,
+                run_request_info.cancel_token.clone(),
+            )
+            .await
+            {
+                Ok(context) => context,
+                Err(e) if e.to_string().contains("TestSkip") => {
+                    return Err(Ok(Self::skipped_result(run_request_info)));
+                }
+                Err(e) => {
+                    return Err(Err(Self::run_error(
+                        run_request_info,
+                        run_request_info.request.clone(),
+                        None,
+                        &e,
+                        vec![],
+                    )));
+                }
+            };
+            secrets.extend(secret_values(&context.envs));
+            for log in context.logger.logs.iter() {
+                logger.logs.push(log.clone());
+            }
+        }
+        Ok(context)
+    }
+    /// Runs the test scripts on `response`, starting from the `test_result` the sender already
+    /// collected, and builds the result of the run.
+    async fn run_test_scripts(
+        run_request_info: &RunRequestInfo,
+        pre_request_context: Context,
+        request: Request,
+        mut response: Response,
+        mut test_result: TestResult,
+        secrets: &mut Vec<String>,
+    ) -> Result<TestRunResult, TestRunError> {
+        let mut env_changes = pre_request_context.env_changes.clone();
+        if run_request_info.test_scripts.len() > 0 {
+            let mut test_context = pre_request_context.clone();
+            test_context.response = JsResponse::from_data_response(response.clone());
+            test_context.logger = Logger::default();
+            test_context.test_result = test_result.clone();
+            response
                 .logger
-                .add_info("System".to_string(), "Run pre-request-scripts".to_string());
-            pre_request_context_result =
-                ScriptRuntime::run_async(run_request_info.pre_request_scripts, default_context)
-                    .await;
+                .add_info("System".to_string(), "Run Test-script".to_string());
+            match ScriptRuntime::run_async(
+                run_request_info.test_scripts.clone(),
+                test_context,
+                run_request_info.cancel_token.clone(),
+            )
+            .await
+            {
+                Ok(test_context) => {
+                    secrets.extend(secret_values(&test_context.envs));
+                    for log in test_context.logger.logs.iter() {
+                        response.logger.logs.push(log.clone());
+                    }
+                    test_result = test_context.test_result.clone();
+                    env_changes = test_context.env_changes.clone();
+                }
+                Err(e) if e.to_string().contains("TestSkip") => {
+                    test_result.status = TestStatus::SKIP;
+                    for test_info in test_result.test_info_list.iter_mut() {
+                        test_info.status = TestStatus::SKIP;
+                    }
+                }
+                Err(e) => {
+                    Self::log_script_error(&mut response.logger, &e);
+                    return Err(Self::run_error(
+                        run_request_info,
+                        request,
+                        Some(response),
+                        &e,
+                        env_changes,
+                    ));
+                }
+            }
+        }
+        Ok(TestRunResult {
+            request,
+            response: Some(response),
+            test_result,
+            collection_path: run_request_info.collection_path.clone(),
+            request_name: run_request_info.request_name.clone(),
+            testcase: run_request_info.testcase.clone(),
+            env_changes,
+            script_changes: ScriptRequestChanges::diff(
+                &run_request_info.request,
+                &pre_request_context.request,
+            ),
+        })
+    }
+    fn skipped_result(run_request_info: &RunRequestInfo) -> TestRunResult {
+        let mut test_result = TestResult::default();
+        test_result.status = TestStatus::SKIP;
+        TestRunResult {
+            request: run_request_info.request.clone(),
+            response: None,
+            test_result,
+            collection_path: run_request_info.collection_path.clone(),
+            request_name: run_request_info.request_name.clone(),
+            testcase: run_request_info.testcase.clone(),
+            env_changes: vec![],
+            script_changes: Default::default(),
         }
-        match pre_request_context_result {
-            Ok(pre_request_context) => {
-                for log in pre_request_context.logger.logs.iter() {
-                    logger.logs.push(log.clone());
+    }
+    fn run_error(
+        run_request_info: &RunRequestInfo,
+        request: Request,
+        response: Option<Response>,
+        e: &anyhow::Error,
+        env_changes: Vec<EnvironmentChange>,
+    ) -> TestRunError {
+        TestRunError {
+            request,
+            response,
+            collection_path: run_request_info.collection_path.clone(),
+            request_name: run_request_info.request_name.clone(),
+            testcase: run_request_info.testcase.clone(),
+            error: e.to_string(),
+            script_error: ScriptError::find(e),
+            env_changes,
+        }
+    }
+    pub async fn run_request_info_async(
+        mut run_request_info: RunRequestInfo,
+        clients: HttpClients,
+    ) -> Result<TestRunResult, TestRunError> {
+        // folder and request file variables are layered after the workspace ones were resolved
+        if let Some(workspace_dir) = run_request_info.workspace_dir.clone() {
+            resolve_file_paths(&mut run_request_info.envs, &workspace_dir);
+        }
+        let cancel_token = run_request_info.cancel_token.clone();
+        // scripts may set secrets, the ones of the last finished script context are added
+        let mut secrets = secret_values(&run_request_info.envs);
+        let mut result = if run_request_info.websocket_scenario.is_some() {
+            Self::send_websocket_with_script_async(run_request_info, clients, &mut secrets).await
+        } else {
+            Self::send_rest_with_script_async(run_request_info, clients, &mut secrets).await
+        };
+        Self::mask_secrets(&mut result, &secrets);
+        match result {
+            Err(e) if cancel_token.is_cancelled() => Ok(Self::cancelled_result(e)),
+            _ => result,
+        }
+    }
+    /// Masks the secret variable values in the logs, assert messages and errors of a result.
+    fn mask_secrets(result: &mut Result<TestRunResult, TestRunError>, secrets: &Vec<String>) {
+        if secrets.is_empty() {
+            return;
+        }
+        match result {
+            Ok(r) => {
+                if let Some(response) = r.response.as_mut() {
+                    response.logger.mask(secrets);
                 }
-                logger.add_info("System".to_string(),format!("Envs: \n{}",serde_yaml::to_string(&pre_request_context.envs).unwrap()));
-                let build_request = RestSender::build_request(
-                    pre_request_context.request.clone(),
-                    pre_request_context.envs.clone(),
-                );
-                logger.add_info(
-                    "Fetch".to_string(),
-                    format!("start fetch request: \n{}", serde_yaml::to_string(&build_request).unwrap()),
-                );
-                match RestSender::reqwest_async_send(build_request, client).await {
-                    Ok((after_request, response)) => {
-                        let mut after_response = response;
-                        logger.add_info(
-                            "Fetch".to_string(),
-                            "get response".to_string(),
-                        );
-                        after_response.logger = logger;
-                        let mut test_result: TestResult = Default::default();
-                        let mut test_context = pre_request_context.clone();
-                        test_context.response =
-                            JsResponse::from_data_response(after_response.clone());
-                        test_context.logger = Logger::default();
-                        if run_request_info.test_scripts.len() > 0 {
-                            after_response
-                                .logger
-                                .add_info("System".to_string(), "Run Test-script".to_string());
-                            pre_request_context_result = ScriptRuntime::run_async(
-                                run_request_info.test_scripts,
-                                test_context,
-                            )
-                            .await;
-                            match pre_request_context_result {
-                                Ok(test_context) => {
-                                    for log in test_context.logger.logs.iter() {
-                                        after_response.logger.logs.push(log.clone());
-                                    }
-                                    test_result = test_context.test_result.clone();
-                                }
-                                Err(e) => {
-                                    if e.to_string().contains("TestSkip"){
-                                        test_result.status = TestStatus::SKIP;
-                                        for test_info in test_result.test_info_list.iter_mut() {
-                                            test_info.status = TestStatus::SKIP;
-                                        }
-                                    } else {
-                                        return Err(TestRunError {
-                                            request: after_request,
-                                            response: Some(after_response),
-                                            collection_path: run_request_info.collection_path.clone(),
-                                            request_name: run_request_info.request_name,
-                                            testcase: run_request_info.testcase.clone(),
-                                            error: e.to_string(),
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                        Ok(TestRunResult {
-                            request: after_request,
-                            response: Some(after_response),
-                            test_result,
-                            collection_path: run_request_info.collection_path.clone(),
-                            request_name: run_request_info.request_name.clone(),
-                            testcase: run_request_info.testcase.clone(),
-                        })
+                for test_info in r.test_result.test_info_list.iter_mut() {
+                    for assert_result in test_info.results.iter_mut() {
+                        assert_result.msg = mask_secrets(assert_result.msg.as_str(), secrets);
                     }
-                    Err(e) => Err(TestRunError {
-                        request: run_request_info.request.clone(),
-                        response: None,
-                        collection_path: run_request_info.collection_path.clone(),
-                        request_name: run_request_info.request_name,
-                        testcase: run_request_info.testcase.clone(),
-                        error: e.to_string(),
-                    }),
                 }
             }
             Err(e) => {
-                if e.to_string().contains("TestSkip"){
-                    let mut test_result = TestResult::default();
-                    test_result.status = TestStatus::SKIP;
-                    Ok(TestRunResult {
-                        request: run_request_info.request.clone(),
-                        response: None,
-                        test_result,
-                        collection_path: run_request_info.collection_path.clone(),
-                        request_name: run_request_info.request_name.clone(),
-                        testcase: run_request_info.testcase.clone(),
-                    })
-                } else {
-                    Err(TestRunError {
-                        request: run_request_info.request.clone(),
-                        response: None,
-                        collection_path: run_request_info.collection_path.clone(),
-                        request_name: run_request_info.request_name,
-                        testcase: run_request_info.testcase.clone(),
-                        error: e.to_string(),
-                    })
+                if let Some(response) = e.response.as_mut() {
+                    response.logger.mask(secrets);
+                }
+                e.error = mask_secrets(e.error.as_str(), secrets);
+                if let Some(script_error) = e.script_error.as_mut() {
+                    script_error.message = mask_secrets(script_error.message.as_str(), secrets);
+                    script_error.stack = mask_secrets(script_error.stack.as_str(), secrets);
                 }
-            },
+            }
+        }
+    }
+    fn log_script_error(logger: &mut Logger, e: &anyhow::Error) {
+        match ScriptError::find(e) {
+            Some(script_error) => {
+                let mut msg = script_error.to_string();
+                if let Some(source_line) = &script_error.source_line {
+                    msg = format!("{}\n> {}", msg, source_line.trim());
+                }
+                logger.add_error(script_error.scope.clone(), format!("{}\n{}", msg, script_error.stack));
+            }
+            None => logger.add_error("System".to_string(), e.to_string()),
+        }
+    }
+    fn cancelled_result(e: TestRunError) -> TestRunResult {
+        let mut test_result = TestResult::default();
+        test_result.status = TestStatus::CANCELLED;
+        TestRunResult {
+            request: e.request,
+            response: None,
+            test_result,
+            collection_path: e.collection_path,
+            request_name: e.request_name,
+            testcase: e.testcase,
+            env_changes: e.env_changes,
+            script_changes: Default::default(),
+        }
+    }
+    pub async fn send_websocket_with_script_async(
+        run_request_info: RunRequestInfo,
+        clients: HttpClients,
+        secrets: &mut Vec<String>,
+    ) -> Result<TestRunResult, TestRunError> {
+        info!(
+            "start send_websocket_with_script_async: {:?} {}",
+            run_request_info.collection_path, run_request_info.request_name
+        );
+        let mut logger = Logger::default();
+        let pre_request_context =
+            match Self::run_pre_request_scripts(&run_request_info, &clients, &mut logger, secrets)
+                .await
+            {
+                Ok(context) => context,
+                Err(result) => return result,
+            };
+        let mut build_request = RestSender::build_request(
+            pre_request_context.request.clone(),
+            pre_request_context.envs.clone(),
+            &mut logger,
+        );
+        build_request.build_raw_url();
+        let steps: Vec<WebSocketScenarioStep> = run_request_info
+            .websocket_scenario
+            .clone()
+            .unwrap_or_default()
+            .iter()
+            .map(|step| {
+                let mut build_step = step.clone();
+                build_step.content = replace_variable_with_logger(
+                    step.content.clone(),
+                    &pre_request_context.envs,
+                    &mut logger,
+                );
+                build_step.expect_value = replace_variable_with_logger(
+                    step.expect_value.clone(),
+                    &pre_request_context.envs,
+                    &mut logger,
+                );
+                build_step
+            })
+            .collect();
+        logger.add_info(
+            "WebSocket".to_string(),
+            format!("start scenario: {}", build_request.raw_url),
+        );
+        let cancel_token = run_request_info.cancel_token.clone();
+        let scenario_result = tokio::select! {
+            result = WebSocketSender::run_scenario(build_request.clone(), steps, &mut logger) => result,
+            _ = cancel_token.cancelled() => Err(anyhow::anyhow!("Cancelled")),
+        };
+        match scenario_result {
+            Ok((mut response, scenario_result)) => {
+                response.request = build_request.clone();
+                response.logger = logger;
+                Self::run_test_scripts(
+                    &run_request_info,
+                    pre_request_context,
+                    build_request,
+                    response,
+                    scenario_result,
+                    secrets,
+                )
+                .await
+            }
+            Err(e) => Err(Self::run_error(
+                &run_request_info,
+                build_request,
+                None,
+                &e,
+                pre_request_context.env_changes,
+            )),
         }
     }
     pub fn send_rest_with_script_promise(
         &self,
         mut run_request_info: RunRequestInfo,
     ) -> Promise<Result<TestRunResult, TestRunError>> {
-        let client = self.client.clone();
+        let clients = self.clients.clone();
         run_request_info.shared_map = SharedMap::default();
         Promise::spawn_thread("send_with_script", move || {
             let runtime = tokio::runtime::Builder::new_current_thread()
                 .enable_all()
                 .build()
                 .unwrap();
-            runtime.block_on(Self::send_rest_with_script_async(
-                run_request_info,
-                client
-            ))
+            runtime.block_on(Self::run_request_info_async(run_request_info, clients))
         })
     }
 
@@ -267,11 +564,11 @@ impl Runner {
         parent_testcase: Option<Testcase>,
         folder: Rc<RefCell<CollectionFolder>>,
     ) -> Promise<()> {
-        let client = self.client.clone();
+        let clients = self.clients.clone();
         let folder_only_read = CollectionFolderOnlyRead::from(folder);
         let run_request_infos = Self::get_test_group_jobs(envs,script_tree,collection_path,parent_testcase,folder_only_read);
         Promise::spawn_thread("send_with_script", move || {
-            Self::run_test_group_jobs(client,run_request_infos,test_group_run_result.clone(),fast);
+            Self::run_test_group_jobs(clients,run_request_infos,test_group_run_result.clone(),fast);
         })
     }
     pub fn run_test_record_promise(
@@ -283,7 +580,7 @@ impl Runner {
         parent_testcase: Option<Testcase>,
         record: Record,
     ) -> Promise<()> {
-        let client = self.client.clone();
+        let clients = self.clients.clone();
         Promise::spawn_thread("send_with_script", move || {
             let runtime = tokio::runtime::Builder::new_current_thread()
                 .enable_all()
@@ -291,7 +588,7 @@ impl Runner {
                 .unwrap();
             runtime.block_on(async {
                 Self::run_test_record_async(
-                    client.clone(),
+                    clients.clone(),
                     envs.clone(),
                     script_tree.clone(),
                     parent_testcase,
@@ -305,7 +602,7 @@ impl Runner {
     }
 
     async fn run_test_record_async(
-        client: Client,
+        clients: HttpClients,
         envs: BTreeMap<String, EnvironmentItemValue>,
         script_tree: ScriptTree,
         testcase: Option<Testcase>,
@@ -350,34 +647,43 @@ impl Runner {
                 collection_path: Some(collection_path.clone()),
                 request_name: record.name(),
                 request: record.must_get_rest().request.clone(),
-                envs: envs.clone(),
+                envs: Self::get_record_envs(&envs, &record),
                 pre_request_scripts: record_pre_request_parent_script_scopes,
                 test_scripts: record_test_parent_script_scopes,
                 testcase: new_request_testcase.clone(),
+                websocket_scenario: Self::get_websocket_scenario(&record),
+                download: Default::default(),
+                cancel_token: test_group_run_result.read().unwrap().cancel_token.clone(),
+                openapi: script_tree.openapi.clone(),
+                lib_dir: script_tree.lib_dir.clone(),
+                workspace_dir: script_tree.workspace_dir.clone(),
+                script_limits: script_tree.script_limits.clone(),
+                environment: script_tree.environment.clone(),
+                operation_id: record.must_get_rest().operation_id.clone(),
             };
             run_request_infos.push(run_request_info)
         }
         let mut jobs = vec![];
         for run_request_info in run_request_infos.iter() {
-            let _client = client.clone();
+            let _clients = clients.clone();
             let _run_request_info = run_request_info.clone();
             let _shared_map = shared_map.clone();
-            jobs.push(Self::send_rest_with_script_async(
+            jobs.push(Self::run_request_info_async(
                 _run_request_info,
-                _client
+                _clients
             ));
         }
         let results = join_all(jobs).await;
         test_group_run_result.write().unwrap().add_results(results);
     }
-    pub fn run_test_group_jobs(client: Client,run_request_infos:Vec<RunRequestInfo>, test_group_run_result: Arc<RwLock<TestGroupRunResults>>,fast:bool){
+    pub fn run_test_group_jobs(clients: HttpClients,run_request_infos:Vec<RunRequestInfo>, test_group_run_result: Arc<RwLock<TestGroupRunResults>>,fast:bool){
         let pool = ThreadPoolBuilder::new().num_threads(20).build().unwrap();
         if fast {
             pool.scope(|scope| {
                 for run_request_info in run_request_infos {
-                    let _client = client.clone();
+                    let _clients = clients.clone();
                     let _test_group_run_result = test_group_run_result.clone();
-                    Self::run_one_job(_client, _test_group_run_result, scope, run_request_info.clone());
+                    Self::run_one_job(_clients, _test_group_run_result, scope, run_request_info.clone());
                 }
             });
         }else{
@@ -392,9 +698,9 @@ impl Runner {
             groups.iter().for_each(|(_,rs)|{
                 pool.scope(|scope| {
                     for run_request_info in rs {
-                        let _client = client.clone();
+                        let _clients = clients.clone();
                         let _test_group_run_result = test_group_run_result.clone();
-                        Self::run_one_job(_client, _test_group_run_result, scope, run_request_info.clone());
+                        Self::run_one_job(_clients, _test_group_run_result, scope, run_request_info.clone());
                     }
                 });
             })
@@ -402,8 +708,22 @@ impl Runner {
         info!("all test_jobs finish");
     }
 
-    fn run_one_job(client: Client, test_group_run_result: Arc<RwLock<TestGroupRunResults>>, scope: &Scope, run_request_info: RunRequestInfo) {
+    fn run_one_job(clients: HttpClients, test_group_run_result: Arc<RwLock<TestGroupRunResults>>, scope: &Scope, run_request_info: RunRequestInfo) {
+        let mut run_request_info = run_request_info;
+        run_request_info.cancel_token = test_group_run_result.read().unwrap().cancel_token.clone();
         if test_group_run_result.read().unwrap().stop_flag{
+            let mut test_result = TestResult::default();
+            test_result.status = TestStatus::CANCELLED;
+            test_group_run_result.write().unwrap().add_result(Ok(TestRunResult{
+                request: run_request_info.request.clone(),
+                response: None,
+                test_result,
+                collection_path: run_request_info.collection_path.clone(),
+                request_name: run_request_info.request_name.clone(),
+                testcase: run_request_info.testcase.clone(),
+                env_changes: vec![],
+                script_changes: Default::default(),
+            }));
             return;
         }
         scope.spawn(move |_| {
@@ -420,17 +740,33 @@ impl Runner {
                 collection_path: run_request_info.collection_path.clone(),
                 request_name: run_request_info.request_name.clone(),
                 testcase: run_request_info.testcase.clone(),
+                env_changes: vec![],
+                script_changes: Default::default(),
             }));
             runtime.block_on(async {
-                let result = Self::send_rest_with_script_async(
+                let result = Self::run_request_info_async(
                     run_request_info.clone(),
-                    client
+                    clients
                 ).await;
-                info!("job finish:{:?}",result);
+                info!(
+                    "job finish: {:?} {} {}",
+                    run_request_info.collection_path,
+                    run_request_info.request_name,
+                    match &result {
+                        Ok(r) => r.test_result.status.to_string(),
+                        Err(e) => e.error.clone(),
+                    }
+                );
                 test_group_run_result.write().unwrap().add_result(result);
             });
         })
     }
+    fn get_websocket_scenario(record: &Record) -> Option<Vec<WebSocketScenarioStep>> {
+        match record {
+            Record::Rest(_) => None,
+            Record::WebSocket(websocket) => Some(websocket.scenario.clone()),
+        }
+    }
     pub fn get_test_group_jobs(
         envs: BTreeMap<String, EnvironmentItemValue>,
         script_tree: ScriptTree,
@@ -462,6 +798,17 @@ impl Runner {
         }
         return run_request_infos;
     }
+    /// `envs` already holds the variables of the folders above the record.
+    fn get_record_envs(
+        envs: &BTreeMap<String, EnvironmentItemValue>,
+        record: &Record,
+    ) -> BTreeMap<String, EnvironmentItemValue> {
+        let mut record_envs = envs.clone();
+        record
+            .envs()
+            .layer_over(&mut record_envs, record.name() + " Request");
+        record_envs
+    }
     fn _get_test_group_jobs(
         envs: BTreeMap<String, EnvironmentItemValue>,
         script_tree: ScriptTree,
@@ -478,11 +825,15 @@ impl Runner {
                 let mut testcase = Testcase::default();
                 child_testcases.insert(testcase.name.clone(), testcase);
             }
+            let mut child_envs = envs.clone();
+            child_folder
+                .envs
+                .layer_over(&mut child_envs, child_folder.get_path() + " Folder");
             for (name, child_testcase) in child_testcases.iter() {
                 let mut merge_testcase = child_testcase.clone();
                 merge_testcase.merge(child_folder.name.clone(), &testcase);
                 let mut result = Self::_get_test_group_jobs(
-                    envs.clone(),
+                    child_envs.clone(),
                     script_tree.clone(),
                     merge_testcase,
                     collection_path.clone() + "/" + name,
@@ -524,10 +875,19 @@ impl Runner {
                     collection_path: Some(collection_path.clone()),
                     request_name: record.name(),
                     request: record.must_get_rest().request.clone(),
-                    envs: envs.clone(),
+                    envs: Self::get_record_envs(&envs, record),
                     pre_request_scripts: record_pre_request_parent_script_scopes,
                     test_scripts: record_test_parent_script_scopes,
                     testcase: new_request_testcase.clone(),
+                    websocket_scenario: Self::get_websocket_scenario(record),
+                    download: Default::default(),
+                    cancel_token: Default::default(),
+                    openapi: script_tree.openapi.clone(),
+                    lib_dir: script_tree.lib_dir.clone(),
+                    workspace_dir: script_tree.workspace_dir.clone(),
+                    script_limits: script_tree.script_limits.clone(),
+                    environment: script_tree.environment.clone(),
+                    operation_id: record.must_get_rest().operation_id.clone(),
                 };
                 run_request_infos.push(run_request_info)
             }
@@ -538,28 +898,44 @@ impl Runner {
 #[derive(Default, Clone)]
 pub struct TestGroupRunResults {
     pub stop_flag:bool,
+    pub cancel_token: CancellationToken,
     pub results: HashMap<String, Result<TestRunResult, TestRunError>>,
+    env_changes: Vec<(Option<String>, EnvironmentChange)>,
 }
 
 impl TestGroupRunResults {
     pub fn stop(&mut self){
         self.stop_flag = true;
+        self.cancel_token.cancel();
     }
     pub fn restart(&mut self){
         self.stop_flag = false;
+        self.cancel_token = CancellationToken::new();
     }
     pub fn add_result(&mut self, result: Result<TestRunResult, TestRunError>) {
         match &result {
             Ok(r) => {
+                for change in r.env_changes.iter() {
+                    self.env_changes
+                        .push((r.collection_path.clone(), change.clone()));
+                }
                 let key = r.testcase.get_testcase_path().join("/");
                 self.results.insert(key, result.clone());
             }
             Err(e) => {
+                for change in e.env_changes.iter() {
+                    self.env_changes
+                        .push((e.collection_path.clone(), change.clone()));
+                }
                 let key = e.testcase.get_testcase_path().join("/");
                 self.results.insert(key, result.clone());
             }
         };
     }
+    /// Drains the variable writes of finished requests so they are persisted only once.
+    pub fn take_env_changes(&mut self) -> Vec<(Option<String>, EnvironmentChange)> {
+        std::mem::take(&mut self.env_changes)
+    }
     pub fn add_results(&mut self, results: Vec<Result<TestRunResult, TestRunError>>) {
         for result in results.iter() {
             self.add_result(result.clone());
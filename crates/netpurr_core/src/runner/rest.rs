@@ -1,25 +1,40 @@
 use std::collections::{BTreeMap, HashMap};
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
+use deno_core::futures::StreamExt;
 use log::info;
 use reqwest::{Body, Client, multipart};
-use reqwest::header::CONTENT_TYPE;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
 use reqwest::Method;
 use reqwest::multipart::Part;
 use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
 use tokio_tungstenite::tungstenite::http::Uri;
 use tokio_util::codec::{BytesCodec, FramedRead};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
 
 use crate::data::environment::EnvironmentItemValue;
 use crate::data::http;
 use crate::data::http::{
-    BodyRawType, BodyType, Header, HttpBody, LockWith, MultipartDataType, PathVariables, QueryParam,
+    BodyRawType, BodyType, ContentEncoding, Header, HttpBody, LockWith, MultipartDataType,
+    PathVariables, QueryParam, ResponseDownload,
 };
 use crate::data::logger::Logger;
+use crate::utils::{replace_file_path_variable_with_logger, replace_variable_with_logger};
+
+// Bodies larger than this are streamed to a file instead of being kept in memory.
+pub const STREAM_TO_FILE_THRESHOLD: usize = 10 * 1024 * 1024;
+pub const RESPONSE_PREVIEW_SIZE: usize = 1024 * 1024;
+// Clients have no total timeout so large downloads can take as long as they need, a request
+// fails when the response headers or the next body chunk take longer than these.
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+pub const READ_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
 
 #[derive(Default, Clone, PartialEq, Eq, Debug)]
 pub struct RestSender {}
@@ -28,15 +43,13 @@ impl RestSender {
     pub async fn reqwest_async_send(
         request: http::Request,
         client: Client,
+        download: ResponseDownload,
+        cancel_token: CancellationToken,
     ) -> anyhow::Result<(http::Request, http::Response)> {
         let reqwest_request = Self::build_reqwest_request(request.clone()).await?;
         let mut new_request = request.clone();
         for (hn, hv) in reqwest_request.headers().iter() {
-            info!(
-                "set header {}:{}",
-                hn.as_str(),
-                hv.to_str().unwrap_or_default()
-            );
+            info!("set header {}", hn.as_str());
             if new_request
                 .headers
                 .iter()
@@ -56,22 +69,149 @@ impl RestSender {
             }
         }
         let start_time = Instant::now();
-        let reqwest_response = client.execute(reqwest_request).await?;
+        let reqwest_response = tokio::select! {
+            response = tokio::time::timeout(RESPONSE_TIMEOUT, client.execute(reqwest_request)) => {
+                response.map_err(|_| anyhow!("No response in {}s", RESPONSE_TIMEOUT.as_secs()))??
+            }
+            _ = cancel_token.cancelled() => return Err(anyhow!("Cancelled")),
+        };
         let total_time = start_time.elapsed();
+        let headers = Header::new_from_map(reqwest_response.headers());
+        let status = reqwest_response.status();
+        let http_version = format!("{:?}", reqwest_response.version());
+        let url = reqwest_response.url().to_string();
+        // with auto decompression disabled the raw body is kept with its Content-Encoding
+        let encoding = if request.disable_decompression {
+            reqwest_response
+                .headers()
+                .get(CONTENT_ENCODING)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| ContentEncoding::from_str(v.trim().to_lowercase().as_str()).ok())
+                .filter(|e| *e != ContentEncoding::NONE)
+        } else {
+            None
+        };
+        let body =
+            Self::read_body(reqwest_response, &download, &cancel_token, encoding).await?;
         Ok((
             new_request,
             http::Response {
                 request: request.clone(),
-                headers: Header::new_from_map(reqwest_response.headers()),
-                status: reqwest_response.status().as_u16(),
-                status_text: reqwest_response.status().to_string(),
+                headers,
+                status: status.as_u16(),
+                status_text: status.to_string(),
                 elapsed_time: total_time.as_millis(),
+                http_version,
+                url,
                 logger: Logger::default(),
-                body: Arc::new(HttpBody::new(reqwest_response.bytes().await?.to_vec())),
+                body: Arc::new(body),
             },
         ))
     }
 
+    async fn read_body(
+        reqwest_response: reqwest::Response,
+        download: &ResponseDownload,
+        cancel_token: &CancellationToken,
+        encoding: Option<ContentEncoding>,
+    ) -> anyhow::Result<HttpBody> {
+        download.set_total(reqwest_response.content_length());
+        let mut stream = reqwest_response.bytes_stream();
+        let mut buffer: Vec<u8> = vec![];
+        let mut size = 0;
+        let mut response_file: Option<(PathBuf, File)> = None;
+        loop {
+            let next = tokio::select! {
+                next = tokio::time::timeout(READ_IDLE_TIMEOUT, stream.next()) => {
+                    match next {
+                        Ok(next) => next,
+                        Err(_) => {
+                            if let Some((path, _)) = response_file {
+                                let _ = tokio::fs::remove_file(path).await;
+                            }
+                            return Err(anyhow!(
+                                "No response data in {}s",
+                                READ_IDLE_TIMEOUT.as_secs()
+                            ));
+                        }
+                    }
+                }
+                _ = cancel_token.cancelled() => {
+                    if let Some((path, _)) = response_file {
+                        let _ = tokio::fs::remove_file(path).await;
+                    }
+                    return Err(anyhow!("Cancelled"));
+                }
+            };
+            let chunk = match next {
+                None => break,
+                Some(chunk) => chunk?,
+            };
+            size += chunk.len();
+            download.set_received(size);
+            match &mut response_file {
+                Some((_, file)) => file.write_all(&chunk).await?,
+                None => {
+                    buffer.extend_from_slice(&chunk);
+                    if buffer.len() > STREAM_TO_FILE_THRESHOLD {
+                        let dir = download.get_dir();
+                        tokio::fs::create_dir_all(dir.clone()).await?;
+                        let path = dir.join(Uuid::new_v4().to_string());
+                        let mut file = File::create(path.clone()).await?;
+                        file.write_all(&buffer).await?;
+                        buffer.truncate(RESPONSE_PREVIEW_SIZE);
+                        response_file = Some((path, file));
+                    }
+                }
+            }
+        }
+        let mut body = match response_file {
+            None => HttpBody::new(buffer),
+            Some((path, mut file)) => {
+                file.flush().await?;
+                info!("response body stream to file {:?}, size {}", path, size);
+                HttpBody::new_with_file(buffer, size, path)
+            }
+        };
+        if let Some(encoding) = encoding {
+            let decoded_size = if body.is_truncated() {
+                std::fs::File::open(body.response_file.as_str())
+                    .and_then(|file| encoding.decoded_size(std::io::BufReader::new(file)))
+            } else {
+                encoding.decoded_size(body.to_vec().as_slice())
+            };
+            body.decoded_size = decoded_size.ok().map(|size| size as usize);
+            body.content_encoding = encoding;
+        }
+        Ok(body)
+    }
+
+    pub fn encode_body(bytes: &[u8], encoding: &ContentEncoding) -> anyhow::Result<Vec<u8>> {
+        match encoding {
+            ContentEncoding::NONE => Ok(bytes.to_vec()),
+            ContentEncoding::GZIP => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(bytes)?;
+                Ok(encoder.finish()?)
+            }
+            ContentEncoding::DEFLATE => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(bytes)?;
+                Ok(encoder.finish()?)
+            }
+            ContentEncoding::BR => {
+                let mut encoded = vec![];
+                {
+                    let mut encoder = brotli::CompressorWriter::new(&mut encoded, 4096, 11, 22);
+                    encoder.write_all(bytes)?;
+                }
+                Ok(encoded)
+            }
+        }
+    }
+
     pub async fn build_reqwest_request(request: http::Request) -> anyhow::Result<reqwest::Request> {
         let client = Client::new();
         let method = Method::from_str(request.method.to_string().to_uppercase().as_str())
@@ -135,37 +275,32 @@ impl RestSender {
                     .filter(|h| h.key.to_lowercase() == "content-type")
                     .last();
 
-                match request.body.body_raw_type {
-                    BodyRawType::TEXT => {
-                        if content_type.is_none() {
-                            builder = builder.header(CONTENT_TYPE, "text/plain");
-                        }
-                        builder = builder.body(request.body.body_str);
-                    }
-                    BodyRawType::JSON => {
-                        if content_type.is_none() {
-                            builder = builder.header(CONTENT_TYPE, "application/json");
-                        }
-                        builder = builder.body(request.body.body_str);
-                    }
-                    BodyRawType::HTML => {
-                        if content_type.is_none() {
-                            builder = builder.header(CONTENT_TYPE, "text/html");
-                        }
-                        builder = builder.body(request.body.body_str);
-                    }
-                    BodyRawType::XML => {
-                        if content_type.is_none() {
-                            builder = builder.header(CONTENT_TYPE, "application/xml");
-                        }
-                        builder = builder.body(request.body.body_str);
-                    }
-                    BodyRawType::JavaScript => {
-                        if content_type.is_none() {
-                            builder = builder.header(CONTENT_TYPE, "application/javascript");
-                        }
-                        builder = builder.body(request.body.body_str);
+                let default_content_type = match request.body.body_raw_type {
+                    BodyRawType::TEXT => "text/plain",
+                    BodyRawType::JSON => "application/json",
+                    BodyRawType::HTML => "text/html",
+                    BodyRawType::XML => "application/xml",
+                    BodyRawType::JavaScript => "application/javascript",
+                };
+                if content_type.is_none() {
+                    builder = builder.header(CONTENT_TYPE, default_content_type);
+                }
+                if request.body.body_encoding == ContentEncoding::NONE {
+                    builder = builder.body(request.body.body_str);
+                } else {
+                    let content_encoding = request
+                        .headers
+                        .iter()
+                        .filter(|h| h.key.to_lowercase() == "content-encoding")
+                        .last();
+                    if content_encoding.is_none() {
+                        builder = builder
+                            .header(CONTENT_ENCODING, request.body.body_encoding.to_string());
                     }
+                    builder = builder.body(Self::encode_body(
+                        request.body.body_str.as_bytes(),
+                        &request.body.body_encoding,
+                    )?);
                 }
             }
             BodyType::BINARY => {
@@ -190,24 +325,33 @@ impl RestSender {
     pub(crate) fn build_request(
         request: http::Request,
         envs: BTreeMap<String, EnvironmentItemValue>,
+        logger: &mut Logger,
     ) -> http::Request {
         let mut build_request = request.clone();
-        build_request.params = Self::build_query_params(request.params.clone(), &envs);
+        build_request.params = Self::build_query_params(request.params.clone(), &envs, logger);
         build_request.base_url = Self::build_base_url(
             request.base_url.clone(),
             request.path_variables.clone(),
             &envs,
+            logger,
         );
-        build_request.headers = Self::build_header(request.headers.clone(), &envs);
+        build_request.headers = Self::build_header(request.headers.clone(), &envs, logger);
         build_request.body.body_str =
-            crate::utils::replace_variable(build_request.body.body_str, envs.clone());
+            replace_variable_with_logger(build_request.body.body_str, &envs, logger);
         for md in build_request.body.body_xxx_form.iter_mut() {
-            md.key = crate::utils::replace_variable(md.key.clone(), envs.clone());
-            md.value = crate::utils::replace_variable(md.value.clone(), envs.clone());
+            md.key = replace_variable_with_logger(md.key.clone(), &envs, logger);
+            md.value = replace_variable_with_logger(md.value.clone(), &envs, logger);
         }
         for md in build_request.body.body_form_data.iter_mut() {
-            md.key = crate::utils::replace_variable(md.key.clone(), envs.clone());
-            md.value = crate::utils::replace_variable(md.value.clone(), envs.clone());
+            md.key = replace_variable_with_logger(md.key.clone(), &envs, logger);
+            md.value = match md.data_type {
+                MultipartDataType::FILE => {
+                    replace_file_path_variable_with_logger(md.value.clone(), &envs, logger)
+                }
+                MultipartDataType::TEXT => {
+                    replace_variable_with_logger(md.value.clone(), &envs, logger)
+                }
+            };
         }
         build_request
     }
@@ -215,13 +359,14 @@ impl RestSender {
     fn build_header(
         headers: Vec<Header>,
         envs: &BTreeMap<String, EnvironmentItemValue>,
+        logger: &mut Logger,
     ) -> Vec<Header> {
         headers
             .iter()
             .filter(|h| h.enable)
             .map(|h| Header {
                 key: h.key.clone(),
-                value: crate::utils::replace_variable(h.value.clone(), envs.clone()),
+                value: replace_variable_with_logger(h.value.clone(), envs, logger),
                 desc: h.desc.clone(),
                 enable: h.enable,
                 lock_with: h.lock_with.clone(),
@@ -232,13 +377,14 @@ impl RestSender {
     fn build_query_params(
         query_params: Vec<QueryParam>,
         envs: &BTreeMap<String, EnvironmentItemValue>,
+        logger: &mut Logger,
     ) -> Vec<QueryParam> {
         query_params
             .iter()
             .filter(|q| q.enable)
             .map(|q| QueryParam {
                 key: q.key.clone(),
-                value: crate::utils::replace_variable(q.value.clone(), envs.clone()),
+                value: replace_variable_with_logger(q.value.clone(), envs, logger),
                 desc: q.desc.clone(),
                 enable: q.enable,
                 lock_with: q.lock_with.clone(),
@@ -250,13 +396,14 @@ impl RestSender {
         mut base_url: String,
         path_variables: Vec<PathVariables>,
         envs: &BTreeMap<String, EnvironmentItemValue>,
+        logger: &mut Logger,
     ) -> String {
-        base_url = crate::utils::replace_variable(base_url, envs.clone());
+        base_url = replace_variable_with_logger(base_url, envs, logger);
         let build_path_variables: Vec<PathVariables> = path_variables
             .iter()
             .map(|p| PathVariables {
                 key: p.key.clone(),
-                value: crate::utils::replace_variable(p.value.clone(), envs.clone()),
+                value: replace_variable_with_logger(p.value.clone(), envs, logger),
                 desc: p.desc.clone(),
             })
             .collect();
@@ -86,6 +86,7 @@ impl ResultTreeFolder {
                     TestStatus::FAIL => case_status = TestStatus::FAIL,
                     TestStatus::SKIP => case_status = TestStatus::SKIP,
                     TestStatus::RUNNING => case_status = TestStatus::WAIT,
+                    TestStatus::CANCELLED => case_status = TestStatus::CANCELLED,
                 }
                 case_folders.insert(name.to_string(), child_folder);
             }
@@ -112,6 +113,10 @@ impl ResultTreeFolder {
                                     case_status = TestStatus::FAIL;
                                 }else if request_status == TestStatus::RUNNING {
                                     case_status = TestStatus::RUNNING;
+                                } else if request_status == TestStatus::CANCELLED
+                                    && case_status != TestStatus::FAIL
+                                {
+                                    case_status = TestStatus::CANCELLED;
                                 }
                             }
                             Err(e) => {
@@ -142,7 +147,8 @@ impl ResultTreeFolder {
                 TestStatus::PASS => {}
                 TestStatus::FAIL => folder_status = TestStatus::FAIL,
                 TestStatus::SKIP => folder_status = TestStatus::SKIP,
-                TestStatus::RUNNING => folder_status = TestStatus::WAIT
+                TestStatus::RUNNING => folder_status = TestStatus::WAIT,
+                TestStatus::CANCELLED => folder_status = TestStatus::CANCELLED,
             }
         }
         new_result_tree_folder.status = folder_status.clone();
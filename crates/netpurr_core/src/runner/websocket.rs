@@ -1,16 +1,23 @@
 use std::sync::{Arc, mpsc, Mutex};
 use std::sync::mpsc::{Receiver, Sender};
+use std::time::{Duration, Instant};
 
 use base64::Engine;
 use base64::engine::general_purpose;
 use chrono::Local;
 use deno_core::futures::{SinkExt, StreamExt};
+use regex::Regex;
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::tungstenite::Message;
 
-use crate::data::http::Request;
-use crate::data::websocket::{MessageType, WebSocketMessage, WebSocketSession};
+use crate::data::http::{Request, Response};
+use crate::data::logger::Logger;
+use crate::data::test::{TestResult, TestStatus};
+use crate::data::websocket::{
+    MessageType, WebSocketMatchType, WebSocketMessage, WebSocketScenarioStep, WebSocketSession,
+    WebSocketStepType,
+};
 use crate::data::websocket::WebSocketStatus::{Connect, ConnectError, Connecting, SendError};
 
 #[derive(Default, Clone, PartialEq, Eq, Debug)]
@@ -88,4 +95,221 @@ impl WebSocketSender {
             }
         }
     }
+
+    pub async fn run_scenario(
+        request: Request,
+        steps: Vec<WebSocketScenarioStep>,
+        logger: &mut Logger,
+    ) -> anyhow::Result<(Response, TestResult)> {
+        let start_time = Instant::now();
+        let (ws_stream, handshake_response) = connect_async(request).await?;
+        let mut response = WebSocketSession::build_handshake_response(handshake_response);
+        response.elapsed_time = start_time.elapsed().as_millis();
+        let (mut tx, mut rx) = ws_stream.split();
+        let mut test_result = TestResult::default();
+        for (index, step) in steps.iter().enumerate() {
+            let name = step.get_name(index);
+            test_result.open(name.clone());
+            match step.step_type {
+                WebSocketStepType::Send => {
+                    let message = match step.message_type {
+                        MessageType::Text => Ok(Message::Text(step.content.clone())),
+                        MessageType::Binary => general_purpose::STANDARD
+                            .decode(step.content.clone())
+                            .map(|b| Message::Binary(b))
+                            .map_err(|e| e.to_string()),
+                    };
+                    match message {
+                        Ok(m) => match tx.send(m).await {
+                            Ok(_) => {
+                                logger.add_info("WebSocket".to_string(), format!("send: {}", step.content));
+                                test_result.append(true, "Send message success".to_string());
+                            }
+                            Err(e) => test_result.append(false, format!("Send message failed: {}", e)),
+                        },
+                        Err(e) => test_result.append(false, format!("Send message failed: {}", e)),
+                    }
+                }
+                WebSocketStepType::Expect => {
+                    let (matched, msg) = Self::wait_expect(step, &mut rx, logger).await;
+                    test_result.append(matched, msg);
+                }
+            }
+            test_result.close(name);
+        }
+        if steps.is_empty() {
+            // nothing was tested, so the record must not count as passed
+            logger.add_info("WebSocket".to_string(), "no scenario steps, skipped".to_string());
+            test_result.status = TestStatus::SKIP;
+        }
+        let _ = tx.close().await;
+        Ok((response, test_result))
+    }
+
+    async fn wait_expect<S>(
+        step: &WebSocketScenarioStep,
+        rx: &mut S,
+        logger: &mut Logger,
+    ) -> (bool, String)
+    where
+        S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        let deadline = Instant::now() + Duration::from_millis(step.timeout);
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match tokio::time::timeout(remaining, rx.next()).await {
+                Err(_) => {
+                    return (
+                        false,
+                        format!("No matching message received in {}ms", step.timeout),
+                    )
+                }
+                Ok(None) => return (false, "Connection closed".to_string()),
+                Ok(Some(Err(e))) => return (false, format!("Receive message failed: {}", e)),
+                Ok(Some(Ok(message))) => {
+                    let text = match message {
+                        Message::Text(text) => text,
+                        Message::Binary(b) => general_purpose::STANDARD.encode(b),
+                        _ => continue,
+                    };
+                    logger.add_info("WebSocket".to_string(), format!("receive: {}", text));
+                    if Self::match_message(step, text.as_str()) {
+                        return (true, format!("Receive matching message `{}`", text));
+                    }
+                }
+            }
+        }
+    }
+
+    fn match_message(step: &WebSocketScenarioStep, text: &str) -> bool {
+        match step.match_type {
+            WebSocketMatchType::Regex => Regex::new(step.content.as_str())
+                .map(|re| re.is_match(text))
+                .unwrap_or(false),
+            WebSocketMatchType::JsonPath => match serde_json::from_str(text) {
+                Ok(json) => {
+                    let values = crate::utils::json_path_query(&json, step.content.as_str());
+                    if step.expect_value.is_empty() {
+                        !values.is_empty()
+                    } else {
+                        values.iter().any(|v| match v {
+                            serde_json::Value::String(s) => s == &step.expect_value,
+                            _ => v.to_string() == step.expect_value,
+                        })
+                    }
+                }
+                Err(_) => false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    // Echoes every text message back until the client closes the connection.
+    async fn echo_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    while let Some(Ok(message)) = ws.next().await {
+                        if message.is_text() && ws.send(message).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        format!("ws://{}", addr)
+    }
+
+    fn request(url: String) -> Request {
+        Request {
+            raw_url: url,
+            ..Default::default()
+        }
+    }
+
+    fn send(content: &str) -> WebSocketScenarioStep {
+        WebSocketScenarioStep {
+            step_type: WebSocketStepType::Send,
+            content: content.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn expect(
+        match_type: WebSocketMatchType,
+        content: &str,
+        expect_value: &str,
+    ) -> WebSocketScenarioStep {
+        WebSocketScenarioStep {
+            step_type: WebSocketStepType::Expect,
+            match_type,
+            content: content.to_string(),
+            expect_value: expect_value.to_string(),
+            timeout: 500,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn match_message_regex_and_json_path() {
+        let step = expect(WebSocketMatchType::Regex, "^pong \\d+$", "");
+        assert!(WebSocketSender::match_message(&step, "pong 42"));
+        assert!(!WebSocketSender::match_message(&step, "ping 42"));
+        let step = expect(WebSocketMatchType::JsonPath, "$.type", "pong");
+        assert!(WebSocketSender::match_message(&step, r#"{"type":"pong"}"#));
+        assert!(!WebSocketSender::match_message(&step, r#"{"type":"ping"}"#));
+        assert!(!WebSocketSender::match_message(&step, "not json"));
+        let step = expect(WebSocketMatchType::JsonPath, "$.id", "");
+        assert!(WebSocketSender::match_message(&step, r#"{"id":1}"#));
+        assert!(!WebSocketSender::match_message(&step, r#"{"type":"pong"}"#));
+    }
+
+    #[tokio::test]
+    async fn scenario_passes_on_matching_messages() {
+        let url = echo_server().await;
+        let steps = vec![
+            send("hello"),
+            expect(WebSocketMatchType::Regex, "^hello$", ""),
+            send(r#"{"type":"pong"}"#),
+            expect(WebSocketMatchType::JsonPath, "$.type", "pong"),
+        ];
+        let mut logger = Logger::default();
+        let (_, result) = WebSocketSender::run_scenario(request(url), steps, &mut logger)
+            .await
+            .unwrap();
+        assert_eq!(result.status, TestStatus::PASS);
+        assert_eq!(result.test_info_list.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn scenario_fails_when_expect_times_out() {
+        let url = echo_server().await;
+        let steps = vec![send("hello"), expect(WebSocketMatchType::Regex, "^bye$", "")];
+        let mut logger = Logger::default();
+        let (_, result) = WebSocketSender::run_scenario(request(url), steps, &mut logger)
+            .await
+            .unwrap();
+        assert_eq!(result.status, TestStatus::FAIL);
+        assert_eq!(result.test_info_list[1].status, TestStatus::FAIL);
+    }
+
+    #[tokio::test]
+    async fn empty_scenario_is_skipped() {
+        let url = echo_server().await;
+        let mut logger = Logger::default();
+        let (_, result) = WebSocketSender::run_scenario(request(url), vec![], &mut logger)
+            .await
+            .unwrap();
+        assert_eq!(result.status, TestStatus::SKIP);
+        assert!(result.test_info_list.is_empty());
+    }
 }
@@ -0,0 +1,349 @@
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{
+    BlockCipher, BlockDecrypt, BlockDecryptMut, BlockEncrypt, BlockEncryptMut, KeyIvInit,
+};
+use aes_gcm::aead::consts::U12;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{AesGcm, Nonce};
+use anyhow::anyhow;
+use base64::engine::general_purpose;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use rand::RngCore;
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::signature::{RandomizedSigner, SignatureEncoding, Signer, Verifier};
+use rsa::{pkcs1v15, pss, RsaPrivateKey, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+/// Turns bytes into text, `encoding` is one of `hex`, `base64`, `base64url`, `url` or `utf8`.
+pub fn encode(bytes: &[u8], encoding: &str) -> anyhow::Result<String> {
+    match encoding {
+        "hex" => Ok(hex::encode(bytes)),
+        "base64" => Ok(general_purpose::STANDARD.encode(bytes)),
+        "base64url" => Ok(general_purpose::URL_SAFE_NO_PAD.encode(bytes)),
+        "url" => Ok(urlencoding::encode_binary(bytes).into_owned()),
+        "utf8" => Ok(String::from_utf8(bytes.to_vec())?),
+        _ => Err(anyhow!("encoding `{}` is not supported", encoding)),
+    }
+}
+
+pub fn decode(text: &str, encoding: &str) -> anyhow::Result<Vec<u8>> {
+    match encoding {
+        "hex" => Ok(hex::decode(text)?),
+        "base64" => Ok(general_purpose::STANDARD.decode(text)?),
+        "base64url" => Ok(general_purpose::URL_SAFE_NO_PAD.decode(text.trim_end_matches('='))?),
+        "url" => Ok(urlencoding::decode_binary(text.as_bytes()).into_owned()),
+        "utf8" => Ok(text.as_bytes().to_vec()),
+        _ => Err(anyhow!("encoding `{}` is not supported", encoding)),
+    }
+}
+
+/// Re-encodes text, e.g. `base64` to `hex` to get at the bytes of a base64 string.
+pub fn convert(text: &str, from: &str, to: &str) -> anyhow::Result<String> {
+    encode(&decode(text, from)?, to)
+}
+
+pub fn digest(algorithm: &str, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    match algorithm {
+        "md5" => Ok(Md5::digest(data).to_vec()),
+        "sha1" => Ok(Sha1::digest(data).to_vec()),
+        "sha256" => Ok(Sha256::digest(data).to_vec()),
+        "sha512" => Ok(Sha512::digest(data).to_vec()),
+        _ => Err(anyhow!("digest `{}` is not supported", algorithm)),
+    }
+}
+
+pub fn hmac(algorithm: &str, key: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    match algorithm {
+        "sha1" => Ok(mac::<Hmac<Sha1>>(key, data)),
+        "sha256" => Ok(mac::<Hmac<Sha256>>(key, data)),
+        "sha512" => Ok(mac::<Hmac<Sha512>>(key, data)),
+        _ => Err(anyhow!("hmac `{}` is not supported", algorithm)),
+    }
+}
+
+fn mac<M: Mac + KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // hmac accepts keys of any length
+    let mut mac = <M as KeyInit>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// AES in `gcm` (12 byte iv, tag appended to the ciphertext) or `cbc` (PKCS#7 padding) mode,
+/// the key length picks AES-128, AES-192 or AES-256.
+pub fn aes_encrypt(mode: &str, key: &[u8], iv: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    match (mode, key.len()) {
+        ("gcm", 16) => gcm_encrypt::<aes::Aes128>(key, iv, data),
+        ("gcm", 24) => gcm_encrypt::<aes::Aes192>(key, iv, data),
+        ("gcm", 32) => gcm_encrypt::<aes::Aes256>(key, iv, data),
+        ("cbc", 16) => cbc_encrypt::<aes::Aes128>(key, iv, data),
+        ("cbc", 24) => cbc_encrypt::<aes::Aes192>(key, iv, data),
+        ("cbc", 32) => cbc_encrypt::<aes::Aes256>(key, iv, data),
+        ("gcm", _) | ("cbc", _) => Err(anyhow!("aes key must be 16, 24 or 32 bytes")),
+        _ => Err(anyhow!("aes mode `{}` is not supported", mode)),
+    }
+}
+
+pub fn aes_decrypt(mode: &str, key: &[u8], iv: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    match (mode, key.len()) {
+        ("gcm", 16) => gcm_decrypt::<aes::Aes128>(key, iv, data),
+        ("gcm", 24) => gcm_decrypt::<aes::Aes192>(key, iv, data),
+        ("gcm", 32) => gcm_decrypt::<aes::Aes256>(key, iv, data),
+        ("cbc", 16) => cbc_decrypt::<aes::Aes128>(key, iv, data),
+        ("cbc", 24) => cbc_decrypt::<aes::Aes192>(key, iv, data),
+        ("cbc", 32) => cbc_decrypt::<aes::Aes256>(key, iv, data),
+        ("gcm", _) | ("cbc", _) => Err(anyhow!("aes key must be 16, 24 or 32 bytes")),
+        _ => Err(anyhow!("aes mode `{}` is not supported", mode)),
+    }
+}
+
+fn gcm_cipher<C>(key: &[u8], iv: &[u8]) -> anyhow::Result<AesGcm<C, U12>>
+where
+    AesGcm<C, U12>: KeyInit,
+{
+    if iv.len() != 12 {
+        return Err(anyhow!("aes-gcm iv must be 12 bytes"));
+    }
+    AesGcm::<C, U12>::new_from_slice(key).map_err(|e| anyhow!(e.to_string()))
+}
+
+fn gcm_encrypt<C>(key: &[u8], iv: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>>
+where
+    AesGcm<C, U12>: KeyInit + Aead,
+{
+    gcm_cipher::<C>(key, iv)?
+        .encrypt(Nonce::from_slice(iv), data)
+        .map_err(|_| anyhow!("aes-gcm encrypt failed"))
+}
+
+fn gcm_decrypt<C>(key: &[u8], iv: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>>
+where
+    AesGcm<C, U12>: KeyInit + Aead,
+{
+    gcm_cipher::<C>(key, iv)?
+        .decrypt(Nonce::from_slice(iv), data)
+        .map_err(|_| anyhow!("aes-gcm decrypt failed, the key, iv or tag does not match"))
+}
+
+fn cbc_encrypt<C>(key: &[u8], iv: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>>
+where
+    C: BlockCipher + BlockEncrypt + KeyInit,
+{
+    let encryptor = cbc::Encryptor::<C>::new_from_slices(key, iv)
+        .map_err(|_| anyhow!("aes-cbc iv must be 16 bytes"))?;
+    Ok(encryptor.encrypt_padded_vec_mut::<Pkcs7>(data))
+}
+
+fn cbc_decrypt<C>(key: &[u8], iv: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>>
+where
+    C: BlockCipher + BlockDecrypt + KeyInit,
+{
+    let decryptor = cbc::Decryptor::<C>::new_from_slices(key, iv)
+        .map_err(|_| anyhow!("aes-cbc iv must be 16 bytes"))?;
+    decryptor
+        .decrypt_padded_vec_mut::<Pkcs7>(data)
+        .map_err(|_| anyhow!("aes-cbc decrypt failed, the key or iv does not match"))
+}
+
+/// Signs with `RS256`, `RS512` (PKCS#1 v1.5) or `PS256`, `PS512` (PSS), the key is a PKCS#8
+/// or PKCS#1 PEM.
+pub fn rsa_sign(algorithm: &str, private_key_pem: &str, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let key = RsaPrivateKey::from_pkcs8_pem(private_key_pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(private_key_pem))
+        .map_err(|_| anyhow!("private key is not a PKCS#8 or PKCS#1 PEM"))?;
+    let mut rng = rand::thread_rng();
+    match algorithm {
+        "RS256" => Ok(pkcs1v15::SigningKey::<Sha256>::new(key).sign(data).to_vec()),
+        "RS512" => Ok(pkcs1v15::SigningKey::<Sha512>::new(key).sign(data).to_vec()),
+        "PS256" => Ok(pss::BlindedSigningKey::<Sha256>::new(key)
+            .sign_with_rng(&mut rng, data)
+            .to_vec()),
+        "PS512" => Ok(pss::BlindedSigningKey::<Sha512>::new(key)
+            .sign_with_rng(&mut rng, data)
+            .to_vec()),
+        _ => Err(anyhow!("rsa algorithm `{}` is not supported", algorithm)),
+    }
+}
+
+pub fn rsa_verify(
+    algorithm: &str,
+    public_key_pem: &str,
+    data: &[u8],
+    signature: &[u8],
+) -> anyhow::Result<bool> {
+    let key = RsaPublicKey::from_public_key_pem(public_key_pem)
+        .or_else(|_| RsaPublicKey::from_pkcs1_pem(public_key_pem))
+        .map_err(|_| anyhow!("public key is not a SPKI or PKCS#1 PEM"))?;
+    let verified = match algorithm {
+        "RS256" | "RS512" => {
+            let signature = match pkcs1v15::Signature::try_from(signature) {
+                Ok(signature) => signature,
+                Err(_) => return Ok(false),
+            };
+            if algorithm == "RS256" {
+                pkcs1v15::VerifyingKey::<Sha256>::new(key).verify(data, &signature)
+            } else {
+                pkcs1v15::VerifyingKey::<Sha512>::new(key).verify(data, &signature)
+            }
+        }
+        "PS256" | "PS512" => {
+            let signature = match pss::Signature::try_from(signature) {
+                Ok(signature) => signature,
+                Err(_) => return Ok(false),
+            };
+            if algorithm == "PS256" {
+                pss::VerifyingKey::<Sha256>::new(key).verify(data, &signature)
+            } else {
+                pss::VerifyingKey::<Sha512>::new(key).verify(data, &signature)
+            }
+        }
+        _ => return Err(anyhow!("rsa algorithm `{}` is not supported", algorithm)),
+    };
+    Ok(verified.is_ok())
+}
+
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+
+    use super::*;
+
+    fn hex_of(bytes: anyhow::Result<Vec<u8>>) -> String {
+        hex::encode(bytes.unwrap())
+    }
+
+    #[test]
+    fn digest_vectors() {
+        assert_eq!(
+            hex_of(digest("md5", b"abc")),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            hex_of(digest("sha1", b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex_of(digest("sha256", b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex_of(digest("sha512", b"abc")),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+        assert!(digest("sha3", b"abc").is_err());
+    }
+
+    // RFC 2202 and RFC 4231 test case 2
+    #[test]
+    fn hmac_vectors() {
+        let data = b"what do ya want for nothing?";
+        assert_eq!(
+            hex_of(hmac("sha1", b"Jefe", data)),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+        assert_eq!(
+            hex_of(hmac("sha256", b"Jefe", data)),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex_of(hmac("sha512", b"Jefe", data)),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+             9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+    }
+
+    #[test]
+    fn encodings() {
+        assert_eq!(encode(b"hello", "base64").unwrap(), "aGVsbG8=");
+        assert_eq!(decode("aGVsbG8=", "base64").unwrap(), b"hello");
+        assert_eq!(encode(&[0xfb, 0xff], "base64url").unwrap(), "-_8");
+        assert_eq!(decode("-_8", "base64url").unwrap(), vec![0xfb, 0xff]);
+        assert_eq!(encode(&[0x00, 0xab], "hex").unwrap(), "00ab");
+        assert_eq!(decode("00AB", "hex").unwrap(), vec![0x00, 0xab]);
+        assert_eq!(encode(b"a b&c=d", "url").unwrap(), "a%20b%26c%3Dd");
+        assert_eq!(decode("a%20b%26c%3Dd", "url").unwrap(), b"a b&c=d");
+        assert!(encode(b"", "base32").is_err());
+        assert_eq!(convert("aGVsbG8=", "base64", "hex").unwrap(), "68656c6c6f");
+        assert_eq!(convert("68656c6c6f", "hex", "utf8").unwrap(), "hello");
+        assert!(convert("/w==", "base64", "utf8").is_err());
+    }
+
+    // RFC 4231 test case 1, the key is only printable as hex
+    #[test]
+    fn hmac_with_hex_key() {
+        let key = decode(&"0b".repeat(20), "hex").unwrap();
+        assert_eq!(
+            hex_of(hmac("sha256", &key, b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+    }
+
+    // NIST GCM spec test case 2
+    #[test]
+    fn aes_gcm_vector() {
+        let key = [0u8; 16];
+        let iv = [0u8; 12];
+        let encrypted = aes_encrypt("gcm", &key, &iv, &[0u8; 16]).unwrap();
+        assert_eq!(
+            hex::encode(&encrypted),
+            "0388dace60b6a392f328c2b971b2fe78ab6e47d42cec13bdf53a67b21257bddf"
+        );
+        assert_eq!(
+            aes_decrypt("gcm", &key, &iv, &encrypted).unwrap(),
+            [0u8; 16]
+        );
+        let mut tampered = encrypted.clone();
+        tampered[0] ^= 1;
+        assert!(aes_decrypt("gcm", &key, &iv, &tampered).is_err());
+        assert!(aes_encrypt("gcm", &key, &[0u8; 16], b"").is_err());
+    }
+
+    // NIST SP 800-38A F.2.1, the second block is the PKCS#7 padding
+    #[test]
+    fn aes_cbc_vector() {
+        let key = hex::decode("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+        let iv = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let plaintext = hex::decode("6bc1bee22e409f96e93d7e117393172a").unwrap();
+        let encrypted = aes_encrypt("cbc", &key, &iv, &plaintext).unwrap();
+        assert_eq!(encrypted.len(), 32);
+        assert_eq!(
+            hex::encode(&encrypted[..16]),
+            "7649abac8119b246cee98e9b12e9197d"
+        );
+        assert_eq!(
+            aes_decrypt("cbc", &key, &iv, &encrypted).unwrap(),
+            plaintext
+        );
+        assert!(aes_encrypt("cbc", &key[..10], &iv, &plaintext).is_err());
+    }
+
+    #[test]
+    fn rsa_sign_and_verify() {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let public_key = RsaPublicKey::from(&private_key);
+        let private_pem = private_key.to_pkcs8_pem(LineEnding::LF).unwrap();
+        let public_pem = public_key.to_public_key_pem(LineEnding::LF).unwrap();
+        for algorithm in ["RS256", "RS512", "PS256"] {
+            let signature = rsa_sign(algorithm, &private_pem, b"payload").unwrap();
+            assert!(rsa_verify(algorithm, &public_pem, b"payload", &signature).unwrap());
+            assert!(!rsa_verify(algorithm, &public_pem, b"tampered", &signature).unwrap());
+        }
+        assert!(rsa_sign("RS256", "not a key", b"payload").is_err());
+    }
+
+    #[test]
+    fn random_bytes_length() {
+        assert_eq!(random_bytes(32).len(), 32);
+        assert_ne!(random_bytes(16), random_bytes(16));
+    }
+}
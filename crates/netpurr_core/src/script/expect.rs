@@ -0,0 +1,216 @@
+use serde_json::Value;
+
+const MAX_DISPLAY_LEN: usize = 100;
+
+/// Evaluates one `expect(...)` assertion from runtime.js and builds the message shown in
+/// `TestAssertResult.msg`. Values cross the op boundary as JSON, so primitives are compared here
+/// while `strict.equal` and `match` carry the result runtime.js computed with `===` and
+/// `RegExp.test`.
+pub fn check(assertion: &str, actual: &Value, expected: &Value, negate: bool) -> (bool, String) {
+    let (result, description) = match assertion {
+        "equal" => (values_equal(actual, expected), format!("equal {}", display(expected))),
+        "strict.equal" => (
+            expected["same"].as_bool().unwrap_or(false),
+            format!("strictly equal {}", display(&expected["value"])),
+        ),
+        "deep.equal" => (
+            values_equal(actual, expected),
+            format!("deep equal {}", display(expected)),
+        ),
+        "include" => (include(actual, expected), format!("include {}", display(expected))),
+        "match" => {
+            let pattern = expected["pattern"].as_str().unwrap_or_default();
+            if let Some(error) = expected["error"].as_str() {
+                let msg = format!("expected {} to match {}: {}", display(actual), pattern, error);
+                return (false, msg);
+            }
+            (expected["matched"].as_bool().unwrap_or(false), format!("match {}", pattern))
+        }
+        "property" => {
+            let name = expected["name"].as_str().unwrap_or_default();
+            let property = actual.as_object().and_then(|o| o.get(name));
+            if expected["has_value"].as_bool().unwrap_or(false) {
+                let value = &expected["value"];
+                (
+                    property.map(|p| values_equal(p, value)).unwrap_or(false),
+                    format!("have property '{}' of {}", name, display(value)),
+                )
+            } else {
+                (property.is_some(), format!("have property '{}'", name))
+            }
+        }
+        "above" => compare(actual, expected, "above", |a, b| a > b),
+        "below" => compare(actual, expected, "below", |a, b| a < b),
+        "least" => compare(actual, expected, "at least", |a, b| a >= b),
+        "most" => compare(actual, expected, "at most", |a, b| a <= b),
+        "lengthOf" => {
+            let length = match actual {
+                Value::String(s) => Some(s.chars().count()),
+                Value::Array(a) => Some(a.len()),
+                _ => None,
+            };
+            let result = match (length, expected.as_f64()) {
+                (Some(length), Some(expected)) => length as f64 == expected,
+                _ => false,
+            };
+            let description = match length {
+                Some(length) if !result => {
+                    format!("have length {} but got {}", display(expected), length)
+                }
+                _ => format!("have length {}", display(expected)),
+            };
+            (result, description)
+        }
+        "oneOf" => (
+            expected
+                .as_array()
+                .map(|list| list.iter().any(|v| values_equal(actual, v)))
+                .unwrap_or(false),
+            format!("be one of {}", display(expected)),
+        ),
+        "a" => {
+            let type_name = expected.as_str().unwrap_or_default().to_lowercase();
+            let article = if type_name.starts_with(['a', 'e', 'i', 'o', 'u']) {
+                "an"
+            } else {
+                "a"
+            };
+            (
+                type_of(actual) == type_name,
+                format!("be {} {}", article, type_name),
+            )
+        }
+        "ok" => (truthy(actual), "be truthy".to_string()),
+        "empty" => {
+            let result = match actual {
+                Value::String(s) => s.is_empty(),
+                Value::Array(a) => a.is_empty(),
+                Value::Object(o) => o.is_empty(),
+                _ => false,
+            };
+            (result, "be empty".to_string())
+        }
+        "exist" => (!actual.is_null(), "exist".to_string()),
+        _ => (false, format!("support assertion `{}`", assertion)),
+    };
+    let result = result != negate;
+    let msg = if negate {
+        format!("expected {} to not {}", display(actual), description)
+    } else {
+        format!("expected {} to {}", display(actual), description)
+    };
+    (result, msg)
+}
+
+pub fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| values_equal(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a
+                    .iter()
+                    .all(|(k, v)| b.get(k).map(|bv| values_equal(v, bv)).unwrap_or(false))
+        }
+        _ => a == b,
+    }
+}
+
+fn include(actual: &Value, expected: &Value) -> bool {
+    match actual {
+        Value::String(s) => expected.as_str().map(|e| s.contains(e)).unwrap_or(false),
+        Value::Array(a) => a.iter().any(|v| values_equal(v, expected)),
+        Value::Object(o) => match expected {
+            Value::Object(e) => e
+                .iter()
+                .all(|(k, v)| o.get(k).map(|ov| values_equal(ov, v)).unwrap_or(false)),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().map(|n| n != 0.0 && !n.is_nan()).unwrap_or(true),
+        Value::String(s) => !s.is_empty(),
+        _ => true,
+    }
+}
+
+fn compare(
+    actual: &Value,
+    expected: &Value,
+    name: &str,
+    op: fn(f64, f64) -> bool,
+) -> (bool, String) {
+    let result = match (actual.as_f64(), expected.as_f64()) {
+        (Some(a), Some(b)) => op(a, b),
+        _ => false,
+    };
+    (result, format!("be {} {}", name, display(expected)))
+}
+
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn display(value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => format!("'{}'", s),
+        _ => value.to_string(),
+    };
+    if text.chars().count() > MAX_DISPLAY_LEN {
+        let truncated: String = text.chars().take(MAX_DISPLAY_LEN).collect();
+        format!("{}...", truncated)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn equal_compares_primitives_by_value() {
+        assert!(check("equal", &json!(1), &json!(1.0), false).0);
+        assert!(!check("equal", &json!("1"), &json!(1), false).0);
+        assert!(check("equal", &json!("a"), &json!("b"), true).0);
+    }
+
+    #[test]
+    fn strict_equal_uses_identity_from_runtime() {
+        let expected = json!({"same": false, "value": {"a": 1}});
+        let (result, msg) = check("strict.equal", &json!({"a": 1}), &expected, false);
+        assert!(!result);
+        assert_eq!(msg, "expected {\"a\":1} to strictly equal {\"a\":1}");
+        let expected = json!({"same": true, "value": [1]});
+        assert!(check("strict.equal", &json!([1]), &expected, false).0);
+        assert!(check("deep.equal", &json!({"a": [1]}), &json!({"a": [1]}), false).0);
+    }
+
+    #[test]
+    fn match_uses_the_runtime_result_and_fails_on_invalid_patterns() {
+        let expected = json!({"matched": true, "pattern": "/(?<=a)b/"});
+        let (result, msg) = check("match", &json!("ab"), &expected, false);
+        assert!(result);
+        assert_eq!(msg, "expected 'ab' to match /(?<=a)b/");
+        let expected = json!({"matched": false, "pattern": "/(/", "error": "SyntaxError"});
+        assert!(!check("match", &json!("ab"), &expected, false).0);
+        assert!(!check("match", &json!("ab"), &expected, true).0);
+    }
+}
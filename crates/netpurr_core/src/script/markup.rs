@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use anyhow::Error;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sxd_document::dom::Element;
+use sxd_xpath::nodeset::Node;
+use sxd_xpath::{Context, Factory};
+
+/// An element or node matched by `xml().select` or `html().select`.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MarkupNode {
+    pub name: String,
+    pub text: String,
+    pub attributes: BTreeMap<String, String>,
+    /// Inner html of an html element, empty for xml nodes.
+    pub html: String,
+}
+
+/// Evaluates `xpath` on `xml`, a node set becomes a list of nodes, other results stay
+/// booleans, numbers or strings. `namespaces` maps prefixes used in the expression to uris.
+pub fn xpath(xml: &str, xpath: &str, namespaces: &BTreeMap<String, String>) -> anyhow::Result<Value> {
+    let package = sxd_document::parser::parse(xml)
+        .map_err(|e| Error::msg(format!("parse xml failed: {}", e)))?;
+    let document = package.as_document();
+    let expression = Factory::new()
+        .build(xpath)
+        .map_err(|e| Error::msg(format!("invalid xpath `{}`: {}", xpath, e)))?
+        .ok_or(Error::msg(format!("invalid xpath `{}`", xpath)))?;
+    let mut context = Context::new();
+    for (prefix, uri) in namespaces.iter() {
+        context.set_namespace(prefix, uri);
+    }
+    let value = expression
+        .evaluate(&context, document.root())
+        .map_err(|e| Error::msg(format!("evaluate xpath `{}` failed: {}", xpath, e)))?;
+    Ok(match value {
+        sxd_xpath::Value::Nodeset(nodes) => serde_json::to_value(
+            nodes
+                .document_order()
+                .iter()
+                .map(xml_node)
+                .collect::<Vec<MarkupNode>>(),
+        )?,
+        sxd_xpath::Value::Boolean(b) => Value::Bool(b),
+        sxd_xpath::Value::Number(n) => serde_json::Number::from_f64(n)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        sxd_xpath::Value::String(s) => Value::String(s),
+    })
+}
+
+fn xml_node(node: &Node) -> MarkupNode {
+    MarkupNode {
+        name: node
+            .expanded_name()
+            .map(|name| name.local_part().to_string())
+            .unwrap_or_default(),
+        text: node.string_value(),
+        attributes: match node {
+            Node::Element(element) => xml_attributes(element),
+            _ => BTreeMap::default(),
+        },
+        html: "".to_string(),
+    }
+}
+
+fn xml_attributes(element: &Element) -> BTreeMap<String, String> {
+    element
+        .attributes()
+        .iter()
+        .map(|a| (a.name().local_part().to_string(), a.value().to_string()))
+        .collect()
+}
+
+/// Selects the elements of `html` matching the css selector `css`.
+pub fn select(html: &str, css: &str) -> anyhow::Result<Vec<MarkupNode>> {
+    let selector = Selector::parse(css)
+        .map_err(|e| Error::msg(format!("invalid css selector `{}`: {}", css, e)))?;
+    let document = Html::parse_document(html);
+    Ok(document
+        .select(&selector)
+        .map(|element| MarkupNode {
+            name: element.value().name().to_string(),
+            text: element.text().collect::<String>(),
+            attributes: element
+                .value()
+                .attrs()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            html: element.inner_html(),
+        })
+        .collect())
+}
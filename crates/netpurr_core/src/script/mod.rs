@@ -4,29 +4,77 @@ use std::collections::{BTreeMap, HashMap};
 use std::error;
 use std::fmt::{Display, Formatter};
 use std::ops::Deref;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str::FromStr;
-use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
 
 use anyhow::Error;
-use deno_core::{ExtensionBuilder, FsModuleLoader, ModuleCodeString, Op, op2, OpState};
-use deno_core::{JsRuntime, PollEventLoopOptions};
+use base64::engine::general_purpose;
+use base64::Engine;
+use deno_core::{ExtensionBuilder, ModuleCodeString, Op, op2, OpState, v8};
+use deno_core::{JsRuntime, PollEventLoopOptions, Snapshot};
+use deno_core::error::JsError;
 use deno_core::url::Url;
 use jieba_rs::{Jieba, Keyword, KeywordExtract, TextRank, TfIdf};
+use openapiv3::OpenAPI;
 use poll_promise::Promise;
 use reqwest::{Client, Method};
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
 use tokio::time::sleep;
+use tokio_util::sync::{CancellationToken, DropGuard};
 
+use crate::data::auth::{Auth, AuthType};
 use crate::data::collections::Testcase;
-use crate::data::environment::{EnvironmentItemValue, EnvironmentValueType};
+use crate::data::cookies_manager::Cookie;
+use crate::data::environment::{
+    EnvironmentChange, EnvironmentItemValue, EnvironmentScope, EnvironmentValueType,
+    ENVIRONMENT_GLOBALS,
+};
+use crate::data::environment_function::{EnvFunction, get_env_result};
 use crate::data::http;
-use crate::data::http::{Header, LockWith, QueryParam, Request};
+use crate::data::http::{ContentEncoding, Header, LockWith, QueryParam, Request};
 use crate::data::logger::Logger;
 use crate::data::test::TestResult;
+use crate::script::module_loader::LibModuleLoader;
+
+mod crypto;
+mod expect;
+mod markup;
+mod module_loader;
+mod schema;
+
+static RUNTIME_SNAPSHOT: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/NETPURR_RUNTIME_SNAPSHOT.bin"));
+
+static WATCHER_RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+
+// Records the own properties of `globalThis` once the isolate is built, resetting restores them
+// so a scope can not see what an earlier scope of the chain assigned to `globalThis`. Properties
+// nested inside the built-in objects, like `netpurr.foo`, are not restored.
+static RESET_GLOBALS_JS: &str = r#"((globalThis) => {
+    const baseline = new Map(
+        Reflect.ownKeys(globalThis).map((key) => [key, Object.getOwnPropertyDescriptor(globalThis, key)])
+    );
+    const reset = () => {
+        for (const key of Reflect.ownKeys(globalThis)) {
+            if (!baseline.has(key) && key !== "__netpurr_reset_globals") {
+                delete globalThis[key];
+            }
+        }
+        for (const [key, descriptor] of baseline) {
+            const current = Object.getOwnPropertyDescriptor(globalThis, key);
+            if (current === undefined || current.configurable) {
+                Object.defineProperty(globalThis, key, descriptor);
+            } else if (current.writable && current.value !== descriptor.value) {
+                globalThis[key] = descriptor.value;
+            }
+        }
+    };
+    Object.defineProperty(globalThis, "__netpurr_reset_globals", { value: reset });
+})(globalThis);"#;
 
 #[derive(Default, Clone)]
 pub struct ScriptRuntime {}
@@ -41,6 +89,83 @@ pub struct Context {
     pub shared_map: SharedMap,
     pub logger: Logger,
     pub test_result: TestResult,
+    pub openapi: Option<Arc<OpenAPI>>,
+    pub operation_id: Option<String>,
+    pub env_changes: Vec<EnvironmentChange>,
+    /// The runner's client, so `fetch` shares its cookie store and TLS settings.
+    pub client: Option<Client>,
+    /// Workspace `scripts/` dir that `lib:` imports resolve from.
+    pub lib_dir: Option<PathBuf>,
+    pub script_limits: ScriptLimits,
+    /// Selected environment and collection names, the `Environment` and `Collection` script
+    /// scopes resolve to the variables layered under them.
+    pub environment: Option<String>,
+    pub collection: Option<String>,
+}
+
+impl Context {
+    /// The scope string `EnvironmentConfig::layer_over` gave the variables of a script scope,
+    /// without an active environment the `Environment` scope falls back to globals.
+    fn scope_name(&self, scope: &EnvironmentScope) -> String {
+        match scope {
+            EnvironmentScope::Globals => ENVIRONMENT_GLOBALS.to_string(),
+            EnvironmentScope::Environment => self
+                .environment
+                .clone()
+                .unwrap_or(ENVIRONMENT_GLOBALS.to_string()),
+            EnvironmentScope::Collection => match &self.collection {
+                Some(collection) => format!("{} Collection", collection),
+                None => scope.to_string(),
+            },
+        }
+    }
+
+    /// A script assigning a secret keeps it secret, so the new value is masked as well.
+    fn value_type(&self, key: &str) -> EnvironmentValueType {
+        match self.envs.get(key) {
+            Some(v) if v.value_type == EnvironmentValueType::Secret => EnvironmentValueType::Secret,
+            _ => EnvironmentValueType::String,
+        }
+    }
+}
+
+/// Per run limits of a script chain, `0` turns a limit off.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptLimits {
+    /// Wall-clock time for all scopes of one pre-request or test run.
+    pub timeout_ms: u64,
+    /// Maximum V8 heap of the isolate.
+    pub max_heap_mb: usize,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        ScriptLimits {
+            timeout_ms: 30_000,
+            max_heap_mb: 256,
+        }
+    }
+}
+
+/// Why the isolate was terminated before the script finished.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ScriptTermination {
+    Timeout(u64),
+    HeapLimit(usize),
+}
+
+impl Display for ScriptTermination {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScriptTermination::Timeout(timeout_ms) => {
+                write!(f, "Script timed out after {} ms", timeout_ms)
+            }
+            ScriptTermination::HeapLimit(max_heap_mb) => {
+                write!(f, "Script exceeded the heap limit of {} MB", max_heap_mb)
+            }
+        }
+    }
 }
 
 #[derive(Default, Clone,Debug)]
@@ -60,10 +185,116 @@ pub struct ScriptScope {
     pub script: String,
     pub scope: String,
 }
+/// A script failure mapped back to the scope that raised it, `line` and `column` are 1-based
+/// positions in that scope's script.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScriptError {
+    pub scope: String,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub source_line: Option<String>,
+    pub stack: String,
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(
+                f,
+                "{} (scope `{}` line {}:{})",
+                self.message, self.scope, line, column
+            ),
+            _ => write!(f, "{} (scope `{}`)", self.message, self.scope),
+        }
+    }
+}
+
+impl error::Error for ScriptError {}
+
+impl ScriptError {
+    const MODULE_PREFIX: &'static str = "file:///netpurr/script-";
+
+    fn module_url(index: usize) -> Url {
+        Url::from_file_path(Path::new(
+            format!("/netpurr/script-{}.js", index).as_str(),
+        ))
+        .unwrap()
+    }
+
+    fn module_index(file_name: &str) -> Option<usize> {
+        file_name
+            .strip_prefix(Self::MODULE_PREFIX)?
+            .strip_suffix(".js")?
+            .parse()
+            .ok()
+    }
+
+    /// Maps a `JsError` raised while running `scripts[index]` onto the scope and line that threw,
+    /// other errors (cancel, op errors outside js) are returned unchanged.
+    fn from_js_error(error: Error, scripts: &Vec<ScriptScope>, index: usize) -> Error {
+        let Some(js_error) = error.downcast_ref::<JsError>() else {
+            return error;
+        };
+        let frame = js_error.frames.iter().find_map(|frame| {
+            let file_name = frame.file_name.as_ref()?;
+            let frame_index = Self::module_index(file_name)?;
+            scripts
+                .get(frame_index)
+                .map(|scope| (scope, frame.line_number, frame.column_number))
+        });
+        let (script_scope, line, column) = match frame {
+            Some((scope, line, column)) => (scope, line, column),
+            None => (&scripts[index], None, None),
+        };
+        let line = line.and_then(|l| usize::try_from(l).ok());
+        let column = column.and_then(|c| usize::try_from(c).ok());
+        let source_line = line.and_then(|l| {
+            script_scope
+                .script
+                .lines()
+                .nth(l.saturating_sub(1))
+                .map(|s| s.to_string())
+        });
+        let mut stack = js_error
+            .stack
+            .clone()
+            .unwrap_or(js_error.exception_message.clone());
+        for (i, scope) in scripts.iter().enumerate() {
+            stack = stack.replace(Self::module_url(i).as_str(), scope.scope.as_str());
+        }
+        Error::new(ScriptError {
+            scope: script_scope.scope.clone(),
+            message: js_error.exception_message.clone(),
+            line,
+            column,
+            source_line,
+            stack,
+        })
+    }
+
+    /// The failing line number in `script`, none once the line was edited since the run.
+    pub fn line_in(&self, script: &str) -> Option<usize> {
+        let line = self.line?;
+        let source_line = self.source_line.as_ref()?;
+        (script.lines().nth(line.saturating_sub(1)) == Some(source_line.as_str())).then_some(line)
+    }
+
+    /// The script error carried by a `run_async` error, if any.
+    pub fn find(error: &Error) -> Option<ScriptError> {
+        error.downcast_ref::<ScriptError>().cloned()
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct ScriptTree {
     pub pre_request_parent_script_scopes: BTreeMap<String, Vec<ScriptScope>>,
     pub test_parent_script_scopes: BTreeMap<String, Vec<ScriptScope>>,
+    pub openapi: Option<Arc<OpenAPI>>,
+    pub lib_dir: Option<PathBuf>,
+    pub workspace_dir: Option<PathBuf>,
+    pub script_limits: ScriptLimits,
+    pub environment: Option<String>,
 }
 impl ScriptRuntime {
     pub fn run_block(
@@ -76,18 +307,70 @@ impl ScriptRuntime {
                 .enable_all()
                 .build()
                 .unwrap();
-            runtime.block_on(ScriptRuntime::run_async(scripts, context))
+            runtime.block_on(ScriptRuntime::run_async(
+                scripts,
+                context,
+                CancellationToken::new(),
+            ))
         })
     }
 
     pub async fn run_async(
         scripts: Vec<ScriptScope>,
         mut context: Context,
+        cancel_token: CancellationToken,
     ) -> anyhow::Result<Context> {
-        for script_scope in scripts.iter() {
+        if scripts.is_empty() {
+            return Ok(context);
+        }
+        // One isolate serves every scope of the chain, each scope runs as its own module.
+        let script_limits = context.script_limits.clone();
+        let mut js_runtime = Self::build_js_runtime(context.lib_dir.clone(), &script_limits);
+        js_runtime.execute_script_static("[runjs:globals.js]", RESET_GLOBALS_JS)?;
+        // Cancelled by the caller, or by the watchers once a limit is hit.
+        let run_token = cancel_token.child_token();
+        let termination = Arc::new(Mutex::new(None));
+        Self::watch_heap_limit(
+            &mut js_runtime,
+            &script_limits,
+            termination.clone(),
+            run_token.clone(),
+        );
+        let _finish_guard = Self::watch_cancel(
+            &mut js_runtime,
+            &script_limits,
+            termination.clone(),
+            run_token.clone(),
+        );
+        let stop_error = |scope: &ScriptScope| match termination.lock().unwrap().clone() {
+            Some(termination) => Some(Error::new(ScriptError {
+                scope: scope.scope.clone(),
+                message: termination.to_string(),
+                ..Default::default()
+            })),
+            None => None,
+        };
+        for (index, script_scope) in scripts.iter().enumerate() {
+            if run_token.is_cancelled() {
+                return Err(stop_error(script_scope).unwrap_or(Error::msg("Cancelled")));
+            }
             context.scope_name = script_scope.scope.clone();
-            let step_context =
-                ScriptRuntime::run_js(script_scope.script.clone(), context.clone()).await?;
+            if index > 0 {
+                js_runtime
+                    .execute_script_static("[runjs:globals.js]", "__netpurr_reset_globals()")?;
+            }
+            let step_context = ScriptRuntime::run_js(
+                &mut js_runtime,
+                index,
+                script_scope.script.clone(),
+                context.clone(),
+                run_token.clone(),
+            )
+            .await
+            .map_err(|e| {
+                stop_error(script_scope)
+                    .unwrap_or_else(|| ScriptError::from_js_error(e, &scripts, index))
+            })?;
             context.envs = step_context.envs.clone();
             context.request = step_context.request.clone();
             context.logger = step_context.logger.clone();
@@ -97,13 +380,31 @@ impl ScriptRuntime {
         Ok(context)
     }
 
-    fn build_js_runtime() -> JsRuntime {
+    /// Builds an isolate from the startup snapshot, `runtime.js` is already evaluated in it.
+    pub fn build_js_runtime(lib_dir: Option<PathBuf>, script_limits: &ScriptLimits) -> JsRuntime {
         let runjs_extension = ExtensionBuilder::default()
             .ops(vec![
                 op_set_env::DECL,
                 op_get_env::DECL,
+                op_has_env::DECL,
+                op_get_variable::DECL,
+                op_set_variable::DECL,
+                op_unset_variable::DECL,
+                op_clear_variables::DECL,
                 op_add_params::DECL,
                 op_add_header::DECL,
+                op_request::DECL,
+                op_set_request_method::DECL,
+                op_set_request_url::DECL,
+                op_set_request_header::DECL,
+                op_remove_request_header::DECL,
+                op_set_request_param::DECL,
+                op_remove_request_param::DECL,
+                op_set_request_path_variable::DECL,
+                op_set_request_body::DECL,
+                op_set_request_form::DECL,
+                op_remove_request_form::DECL,
+                op_set_request_auth::DECL,
                 op_log::DECL,
                 op_error::DECL,
                 op_warn::DECL,
@@ -119,6 +420,21 @@ impl ScriptRuntime {
                 op_get_testcase::DECL,
                 op_test_skip::DECL,
                 op_equal::DECL,
+                op_expect::DECL,
+                op_validate_schema::DECL,
+                op_xml_xpath::DECL,
+                op_html_select::DECL,
+                op_crypto_digest::DECL,
+                op_crypto_hmac::DECL,
+                op_crypto_encode::DECL,
+                op_crypto_decode::DECL,
+                op_crypto_aes_encrypt::DECL,
+                op_crypto_aes_decrypt::DECL,
+                op_crypto_rsa_sign::DECL,
+                op_crypto_rsa_verify::DECL,
+                op_crypto_random_bytes::DECL,
+                op_crypto_uuid::DECL,
+                op_faker::DECL,
                 op_nlp_keywords::DECL,
                 op_nlp_tags::DECL,
                 op_nlp_tag_filter::DECL,
@@ -126,34 +442,121 @@ impl ScriptRuntime {
             ])
             .build();
         return JsRuntime::new(deno_core::RuntimeOptions {
-            module_loader: Some(Rc::new(FsModuleLoader)),
+            module_loader: Some(Rc::new(LibModuleLoader::new(lib_dir))),
+            startup_snapshot: Some(Snapshot::Static(RUNTIME_SNAPSHOT)),
             extensions: vec![runjs_extension],
+            create_params: (script_limits.max_heap_mb > 0).then(|| {
+                v8::CreateParams::default().heap_limits(0, script_limits.max_heap_mb * 1024 * 1024)
+            }),
             ..Default::default()
         });
     }
-    async fn run_js(js: String, context: Context) -> anyhow::Result<Context> {
-        let mut js_runtime = Self::build_js_runtime();
+
+    // A busy script blocks the runtime thread, so the isolate is terminated from the shared
+    // watcher runtime, which owns its own thread.
+    fn watch_cancel(
+        js_runtime: &mut JsRuntime,
+        script_limits: &ScriptLimits,
+        termination: Arc<Mutex<Option<ScriptTermination>>>,
+        cancel_token: CancellationToken,
+    ) -> DropGuard {
+        let isolate_handle = js_runtime.v8_isolate().thread_safe_handle();
+        let finish_token = CancellationToken::new();
+        let watch_finish_token = finish_token.clone();
+        let timeout_ms = script_limits.timeout_ms;
+        Self::watcher_runtime().spawn(async move {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    isolate_handle.terminate_execution();
+                }
+                _ = sleep(Duration::from_millis(timeout_ms)), if timeout_ms > 0 => {
+                    termination
+                        .lock()
+                        .unwrap()
+                        .get_or_insert(ScriptTermination::Timeout(timeout_ms));
+                    isolate_handle.terminate_execution();
+                    cancel_token.cancel();
+                }
+                _ = watch_finish_token.cancelled() => {}
+            }
+        });
+        finish_token.drop_guard()
+    }
+
+    fn watcher_runtime() -> &'static tokio::runtime::Runtime {
+        WATCHER_RUNTIME.get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .thread_name("script-watcher")
+                .enable_all()
+                .build()
+                .unwrap()
+        })
+    }
+
+    // V8 aborts the process once the heap limit is reached, so terminate just before it and
+    // raise the limit to leave room for unwinding.
+    fn watch_heap_limit(
+        js_runtime: &mut JsRuntime,
+        script_limits: &ScriptLimits,
+        termination: Arc<Mutex<Option<ScriptTermination>>>,
+        cancel_token: CancellationToken,
+    ) {
+        let max_heap_mb = script_limits.max_heap_mb;
+        if max_heap_mb == 0 {
+            return;
+        }
+        let isolate_handle = js_runtime.v8_isolate().thread_safe_handle();
+        js_runtime.add_near_heap_limit_callback(move |current_limit, _initial_limit| {
+            termination
+                .lock()
+                .unwrap()
+                .get_or_insert(ScriptTermination::HeapLimit(max_heap_mb));
+            isolate_handle.terminate_execution();
+            cancel_token.cancel();
+            current_limit * 2
+        });
+    }
+
+    async fn run_js(
+        js_runtime: &mut JsRuntime,
+        index: usize,
+        js: String,
+        context: Context,
+        cancel_token: CancellationToken,
+    ) -> anyhow::Result<Context> {
         js_runtime.op_state().borrow_mut().put(context);
-        let runtime_init_code = include_str!("resource/runtime.js");
-        js_runtime
-            .execute_script_static("[runjs:runtime.js]", runtime_init_code)
-            .unwrap();
-        let temp = Url::from_file_path(Path::new("/netpurr/pre-request-script.js")).unwrap();
+        let temp = ScriptError::module_url(index);
+        let run_result = tokio::select! {
+            result = Self::evaluate_module(js_runtime, &temp, js) => result,
+            _ = cancel_token.cancelled() => Err(Error::msg("Cancelled")),
+        };
+        if cancel_token.is_cancelled() {
+            return Err(Error::msg("Cancelled"));
+        }
+        run_result?;
+        let op_state = js_runtime.op_state();
+        let new_context = op_state
+            .borrow_mut()
+            .try_take::<Context>()
+            .ok_or(anyhow::Error::msg("get context error"))?;
+        Ok(new_context)
+    }
+
+    async fn evaluate_module(
+        js_runtime: &mut JsRuntime,
+        specifier: &Url,
+        js: String,
+    ) -> anyhow::Result<()> {
         let mod_id = js_runtime
-            .load_main_module(&temp, Some(ModuleCodeString::from(js)))
+            .load_side_module(specifier, Some(ModuleCodeString::from(js)))
             .await?;
         let result = js_runtime.mod_evaluate(mod_id);
         js_runtime
             .run_event_loop(PollEventLoopOptions::default())
             .await?;
         result.await?;
-        let op_state = js_runtime.op_state();
-        let new_context = op_state
-            .borrow()
-            .try_borrow::<Context>()
-            .ok_or(anyhow::Error::msg("get context error"))?
-            .clone();
-        Ok(new_context)
+        Ok(())
     }
 }
 
@@ -260,12 +663,14 @@ fn op_set_env(state: &mut OpState, #[string] key: String, #[string] value: Strin
     match context {
         None => {}
         Some(c) => {
+            let value_type = c.value_type(key.as_str());
             c.envs.insert(
                 key.clone(),
                 EnvironmentItemValue {
                     value: value.clone(),
                     scope: "Script".to_string(),
-                    value_type: EnvironmentValueType::String,
+                    value_type,
+                    ..Default::default()
                 },
             );
             c.logger.add_info(
@@ -276,6 +681,70 @@ fn op_set_env(state: &mut OpState, #[string] key: String, #[string] value: Strin
     }
 }
 
+#[op2]
+fn op_set_variable(
+    state: &mut OpState,
+    #[serde] scope: EnvironmentScope,
+    #[string] key: String,
+    #[string] value: String,
+) {
+    let context = state.try_borrow_mut::<Context>();
+    match context {
+        None => {}
+        Some(c) => {
+            let scope_name = c.scope_name(&scope);
+            let value_type = c.value_type(key.as_str());
+            c.envs.insert(
+                key.clone(),
+                EnvironmentItemValue {
+                    value: value.clone(),
+                    scope: scope_name,
+                    value_type,
+                    ..Default::default()
+                },
+            );
+            c.logger.add_info(
+                c.scope_name.clone(),
+                format!("set {}: `{}` as `{}`", scope, key, value),
+            );
+            c.env_changes
+                .push(EnvironmentChange::Set { scope, key, value });
+        }
+    }
+}
+
+#[op2]
+fn op_unset_variable(state: &mut OpState, #[serde] scope: EnvironmentScope, #[string] key: String) {
+    let context = state.try_borrow_mut::<Context>();
+    match context {
+        None => {}
+        Some(c) => {
+            let scope_name = c.scope_name(&scope);
+            if c.envs.get(key.as_str()).is_some_and(|v| v.scope == scope_name) {
+                c.envs.remove(key.as_str());
+            }
+            c.logger
+                .add_info(c.scope_name.clone(), format!("unset {}: `{}`", scope, key));
+            c.env_changes.push(EnvironmentChange::Unset { scope, key });
+        }
+    }
+}
+
+#[op2]
+fn op_clear_variables(state: &mut OpState, #[serde] scope: EnvironmentScope) {
+    let context = state.try_borrow_mut::<Context>();
+    match context {
+        None => {}
+        Some(c) => {
+            let scope_name = c.scope_name(&scope);
+            c.envs.retain(|_, v| v.scope != scope_name);
+            c.logger
+                .add_info(c.scope_name.clone(), format!("clear {}", scope));
+            c.env_changes.push(EnvironmentChange::Clear { scope });
+        }
+    }
+}
+
 #[op2]
 #[string]
 fn op_get_env(state: &mut OpState, #[string] key: String) -> String {
@@ -293,6 +762,31 @@ fn op_get_env(state: &mut OpState, #[string] key: String) -> String {
     }
 }
 
+#[op2(fast)]
+fn op_has_env(state: &mut OpState, #[string] key: String) -> bool {
+    state
+        .try_borrow::<Context>()
+        .map(|c| c.envs.contains_key(key.as_str()))
+        .unwrap_or(false)
+}
+
+/// The variable only if it is stored in `scope`, `pm.<scope>.get` must not see other scopes.
+#[op2]
+#[string]
+fn op_get_variable(
+    state: &mut OpState,
+    #[serde] scope: EnvironmentScope,
+    #[string] key: String,
+) -> Option<String> {
+    let context = state.try_borrow::<Context>()?;
+    let scope_name = context.scope_name(&scope);
+    context
+        .envs
+        .get(key.as_str())
+        .filter(|v| v.scope == scope_name)
+        .map(|v| v.value.clone())
+}
+
 #[op2(fast)]
 fn op_add_header(state: &mut OpState, #[string] key: String, #[string] value: String) {
     let context = state.try_borrow_mut::<Context>();
@@ -337,6 +831,287 @@ fn op_add_params(state: &mut OpState, #[string] key: String, #[string] value: St
     }
 }
 
+#[op2]
+#[serde]
+fn op_request(state: &mut OpState) -> anyhow::Result<Request> {
+    let context = state
+        .try_borrow_mut::<Context>()
+        .ok_or(Error::msg("context is none"))?;
+    Ok(context.request.clone())
+}
+
+#[op2(fast)]
+fn op_set_request_method(state: &mut OpState, #[string] method: String) -> anyhow::Result<()> {
+    let context = state
+        .try_borrow_mut::<Context>()
+        .ok_or(Error::msg("context is none"))?;
+    context.request.method = http::Method::from_str(method.to_uppercase().as_str())
+        .map_err(|_| Error::msg(format!("method `{}` is not supported", method)))?;
+    context.logger.add_info(
+        context.scope_name.clone(),
+        format!("set method: `{}`", context.request.method),
+    );
+    Ok(())
+}
+
+#[op2(fast)]
+fn op_set_request_url(state: &mut OpState, #[string] url: String) -> anyhow::Result<()> {
+    let context = state
+        .try_borrow_mut::<Context>()
+        .ok_or(Error::msg("context is none"))?;
+    let mut request = context.request.clone();
+    request.raw_url = url.clone();
+    request.parse_raw_url();
+    for param in request.params.iter_mut() {
+        if !context.request.params.contains(param) {
+            param.lock_with = LockWith::LockWithScript;
+        }
+    }
+    context.request = request;
+    context
+        .logger
+        .add_info(context.scope_name.clone(), format!("set url: `{}`", url));
+    Ok(())
+}
+
+#[op2(fast)]
+fn op_set_request_header(state: &mut OpState, #[string] key: String, #[string] value: String) {
+    let context = state.try_borrow_mut::<Context>();
+    match context {
+        None => {}
+        Some(c) => {
+            let mut find = false;
+            for header in c
+                .request
+                .headers
+                .iter_mut()
+                .filter(|h| h.enable && h.key.to_lowercase() == key.to_lowercase())
+            {
+                if header.lock_with == LockWith::LockWithScript {
+                    header.value = value.clone();
+                    find = true;
+                } else {
+                    header.enable = false;
+                }
+            }
+            if !find {
+                c.request.headers.push(Header {
+                    key: key.clone(),
+                    value: value.clone(),
+                    enable: true,
+                    lock_with: LockWith::LockWithScript,
+                    desc: "build with script".to_string(),
+                });
+            }
+            c.logger.add_info(
+                c.scope_name.clone(),
+                format!("set header: `{}` as `{}`", key, value),
+            );
+        }
+    }
+}
+
+#[op2(fast)]
+fn op_remove_request_header(state: &mut OpState, #[string] key: String) {
+    let context = state.try_borrow_mut::<Context>();
+    match context {
+        None => {}
+        Some(c) => {
+            c.request.headers.retain(|h| {
+                h.lock_with != LockWith::LockWithScript || h.key.to_lowercase() != key.to_lowercase()
+            });
+            c.request
+                .headers
+                .iter_mut()
+                .filter(|h| h.key.to_lowercase() == key.to_lowercase())
+                .for_each(|h| h.enable = false);
+            c.logger
+                .add_info(c.scope_name.clone(), format!("remove header: `{}`", key));
+        }
+    }
+}
+
+#[op2(fast)]
+fn op_set_request_param(state: &mut OpState, #[string] key: String, #[string] value: String) {
+    let context = state.try_borrow_mut::<Context>();
+    match context {
+        None => {}
+        Some(c) => {
+            let mut find = false;
+            for param in c
+                .request
+                .params
+                .iter_mut()
+                .filter(|q| q.enable && q.key == key)
+            {
+                if param.lock_with == LockWith::LockWithScript {
+                    param.value = value.clone();
+                    find = true;
+                } else {
+                    param.enable = false;
+                }
+            }
+            if !find {
+                c.request.params.push(QueryParam {
+                    key: key.clone(),
+                    value: value.clone(),
+                    enable: true,
+                    lock_with: LockWith::LockWithScript,
+                    desc: "build with script".to_string(),
+                });
+            }
+            c.logger.add_info(
+                c.scope_name.clone(),
+                format!("set params: `{}` as `{}`", key, value),
+            );
+        }
+    }
+}
+
+#[op2(fast)]
+fn op_remove_request_param(state: &mut OpState, #[string] key: String) {
+    let context = state.try_borrow_mut::<Context>();
+    match context {
+        None => {}
+        Some(c) => {
+            c.request
+                .params
+                .retain(|q| q.lock_with != LockWith::LockWithScript || q.key != key);
+            c.request
+                .params
+                .iter_mut()
+                .filter(|q| q.key == key)
+                .for_each(|q| q.enable = false);
+            c.logger
+                .add_info(c.scope_name.clone(), format!("remove params: `{}`", key));
+        }
+    }
+}
+
+#[op2(fast)]
+fn op_set_request_path_variable(
+    state: &mut OpState,
+    #[string] key: String,
+    #[string] value: String,
+) -> anyhow::Result<()> {
+    let context = state
+        .try_borrow_mut::<Context>()
+        .ok_or(Error::msg("context is none"))?;
+    let path_variable = context
+        .request
+        .path_variables
+        .iter_mut()
+        .find(|p| p.key == key)
+        .ok_or(Error::msg(format!("path variable `{}` is not in the url", key)))?;
+    path_variable.value = value.clone();
+    context.logger.add_info(
+        context.scope_name.clone(),
+        format!("set path variable: `{}` as `{}`", key, value),
+    );
+    Ok(())
+}
+
+#[op2(fast)]
+fn op_set_request_body(state: &mut OpState, #[string] body: String) {
+    let context = state.try_borrow_mut::<Context>();
+    match context {
+        None => {}
+        Some(c) => {
+            c.request.body.body_type = http::BodyType::RAW;
+            c.request.body.body_str = body.clone();
+            c.logger
+                .add_info(c.scope_name.clone(), format!("set body: `{}`", body));
+        }
+    }
+}
+
+fn request_form(request: &mut Request) -> anyhow::Result<&mut Vec<http::MultipartData>> {
+    match request.body.body_type {
+        http::BodyType::FROM_DATA => Ok(&mut request.body.body_form_data),
+        http::BodyType::X_WWW_FROM_URLENCODED => Ok(&mut request.body.body_xxx_form),
+        _ => Err(Error::msg(format!(
+            "request body `{}` is not a form",
+            request.body.body_type
+        ))),
+    }
+}
+
+#[op2(fast)]
+fn op_set_request_form(
+    state: &mut OpState,
+    #[string] key: String,
+    #[string] value: String,
+) -> anyhow::Result<()> {
+    let context = state
+        .try_borrow_mut::<Context>()
+        .ok_or(Error::msg("context is none"))?;
+    let form = request_form(&mut context.request)?;
+    let mut find = false;
+    for data in form.iter_mut().filter(|d| d.enable && d.key == key) {
+        if data.lock_with == LockWith::LockWithScript {
+            data.value = value.clone();
+            find = true;
+        } else {
+            data.enable = false;
+        }
+    }
+    if !find {
+        form.push(http::MultipartData {
+            data_type: http::MultipartDataType::TEXT,
+            key: key.clone(),
+            value: value.clone(),
+            desc: "build with script".to_string(),
+            lock_with: LockWith::LockWithScript,
+            enable: true,
+        });
+    }
+    context.logger.add_info(
+        context.scope_name.clone(),
+        format!("set form: `{}` as `{}`", key, value),
+    );
+    Ok(())
+}
+
+#[op2(fast)]
+fn op_remove_request_form(state: &mut OpState, #[string] key: String) -> anyhow::Result<()> {
+    let context = state
+        .try_borrow_mut::<Context>()
+        .ok_or(Error::msg("context is none"))?;
+    let form = request_form(&mut context.request)?;
+    form.retain(|d| d.lock_with != LockWith::LockWithScript || d.key != key);
+    form.iter_mut()
+        .filter(|d| d.key == key)
+        .for_each(|d| d.enable = false);
+    context
+        .logger
+        .add_info(context.scope_name.clone(), format!("remove form: `{}`", key));
+    Ok(())
+}
+
+#[op2]
+fn op_set_request_auth(state: &mut OpState, #[serde] auth: Auth) {
+    let context = state.try_borrow_mut::<Context>();
+    match context {
+        None => {}
+        Some(c) => {
+            c.request.auth = auth.clone();
+            // inherited auth was already resolved into the header before the script ran
+            if auth.auth_type != AuthType::InheritAuthFromParent {
+                auth.build_head(&mut c.request.headers, c.envs.clone(), Auth::default());
+                c.request
+                    .headers
+                    .iter_mut()
+                    .filter(|h| h.lock_with == LockWith::LockWithAuto && h.key == "Authorization")
+                    .for_each(|h| h.lock_with = LockWith::LockWithScript);
+            }
+            c.logger.add_info(
+                c.scope_name.clone(),
+                format!("set auth: `{}`", auth.auth_type),
+            );
+        }
+    }
+}
+
 #[op2(fast)]
 fn op_log(state: &mut OpState, #[string] msg: String) {
     let context = state.try_borrow_mut::<Context>();
@@ -384,43 +1159,106 @@ pub struct JsResponse {
     status: u16,
     headers: Vec<JsHeader>,
     text: String,
+    http_version: String,
+    /// The body bytes, so binary responses survive the utf8 `text`.
+    base64: String,
+    size: usize,
+    elapsed_time: u64,
+    cookies: Vec<Cookie>,
+    /// Final url after redirects.
+    url: String,
+    /// Large bodies are streamed to `response_file`, `text` and `base64` only hold the preview.
+    truncated: bool,
+    response_file: String,
 }
 
 impl JsResponse {
     pub fn from_data_response(response: http::Response) -> Self {
+        let headers: Vec<JsHeader> = response
+            .headers
+            .iter()
+            .map(|h| JsHeader {
+                name: h.key.clone(),
+                value: h.value.clone(),
+            })
+            .collect();
+        // scripts read the decoded text, `base64` and `size` keep the bytes as received
+        let body = response.body.to_vec();
+        let text = match response.body.content_encoding {
+            ContentEncoding::NONE => String::from_utf8_lossy(&body).to_string(),
+            _ => String::from_utf8_lossy(&response.body.content_encoding.decode_prefix(&body))
+                .to_string(),
+        };
         Self {
             status: response.status,
-            headers: response
-                .headers
-                .iter()
-                .map(|h| JsHeader {
-                    name: h.key.clone(),
-                    value: h.value.clone(),
-                })
-                .collect(),
-            text: String::from_utf8(response.body.to_vec()).unwrap_or("".to_string()),
+            cookies: Self::cookies(&headers),
+            headers,
+            text,
+            http_version: response.http_version.clone(),
+            base64: general_purpose::STANDARD.encode(&body),
+            size: response.body.size,
+            elapsed_time: response.elapsed_time as u64,
+            url: response.url.clone(),
+            truncated: response.body.is_truncated(),
+            response_file: response.body.response_file.clone(),
         }
     }
+
+    fn cookies(headers: &Vec<JsHeader>) -> Vec<Cookie> {
+        headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case("set-cookie"))
+            .map(|h| Cookie::from_raw(h.value.clone()))
+            .collect()
+    }
 }
 #[op2(async)]
 #[serde]
-async fn op_http_fetch(#[serde] request: JsRequest) -> anyhow::Result<JsResponse> {
+async fn op_http_fetch(
+    state: Rc<RefCell<OpState>>,
+    #[serde] request: JsRequest,
+) -> anyhow::Result<JsResponse> {
     let method_enum = Method::from_str(request.method.to_uppercase().as_str())?;
     let mut request_headers = HeaderMap::new();
     for header in request.headers.iter() {
-        request_headers.insert(
-            HeaderName::from_str(header.value.as_str())?,
+        request_headers.append(
+            HeaderName::from_str(header.name.as_str())?,
             HeaderValue::from_str(header.value.as_str())?,
         );
     }
-    let response = Client::builder()
-        .build()?
-        .request(method_enum, request.url)
+    let client = match state.borrow_mut().try_borrow_mut::<Context>() {
+        Some(c) => {
+            c.logger.add_info(
+                "Fetch".to_string(),
+                format!("start fetch: `{} {}`", method_enum, request.url),
+            );
+            c.client.clone().unwrap_or_default()
+        }
+        None => Client::default(),
+    };
+    let mut builder = client
+        .request(method_enum, request.url.clone())
         .headers(request_headers)
-        .body(request.body)
-        .send()
-        .await?;
+        // script fetches keep the whole body in memory, so they keep a total timeout
+        .timeout(Duration::from_secs(60));
+    if !request.body.is_empty() {
+        builder = builder.body(request.body);
+    }
+    let start = Instant::now();
+    let response = match builder.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            if let Some(c) = state.borrow_mut().try_borrow_mut::<Context>() {
+                c.logger.add_error(
+                    "Fetch".to_string(),
+                    format!("fetch `{}` failed: {}", request.url, e),
+                );
+            }
+            return Err(e.into());
+        }
+    };
     let status = response.status().as_u16();
+    let http_version = format!("{:?}", response.version());
     let mut response_headers: Vec<JsHeader> = vec![];
     for (header_name, header_value) in response.headers().iter() {
         response_headers.push(JsHeader {
@@ -428,11 +1266,30 @@ async fn op_http_fetch(#[serde] request: JsRequest) -> anyhow::Result<JsResponse
             value: header_value.to_str()?.to_string(),
         });
     }
-    let text = response.text().await?.clone();
+    let url = response.url().to_string();
+    let body = response.bytes().await?.to_vec();
+    if let Some(c) = state.borrow_mut().try_borrow_mut::<Context>() {
+        c.logger.add_info(
+            "Fetch".to_string(),
+            format!(
+                "get fetch response: `{}` {} in {} ms",
+                request.url,
+                status,
+                start.elapsed().as_millis()
+            ),
+        );
+    }
     let result = JsResponse {
         status,
-        text,
+        text: String::from_utf8_lossy(&body).to_string(),
+        cookies: JsResponse::cookies(&response_headers),
         headers: response_headers,
+        http_version,
+        base64: general_purpose::STANDARD.encode(&body),
+        size: body.len(),
+        elapsed_time: start.elapsed().as_millis() as u64,
+        url,
+        ..Default::default()
     };
     Ok(result)
 }
@@ -479,6 +1336,210 @@ fn op_equal(#[serde] a:serde_json::Value, #[serde] b:serde_json::Value) -> bool
     return a==b;
 }
 
+#[op2]
+fn op_expect(
+    state: &mut OpState,
+    #[string] assertion: String,
+    #[serde] actual: serde_json::Value,
+    #[serde] expected: serde_json::Value,
+    negate: bool,
+) -> bool {
+    let (result, msg) = expect::check(assertion.as_str(), &actual, &expected, negate);
+    if let Some(c) = state.try_borrow_mut::<Context>() {
+        c.test_result.append(result, msg);
+    }
+    result
+}
+
+#[op2]
+#[serde]
+fn op_xml_xpath(
+    #[string] xml: String,
+    #[string] xpath: String,
+    #[serde] namespaces: BTreeMap<String, String>,
+) -> anyhow::Result<serde_json::Value> {
+    markup::xpath(&xml, &xpath, &namespaces)
+}
+
+#[op2]
+#[serde]
+fn op_html_select(
+    #[string] html: String,
+    #[string] css: String,
+) -> anyhow::Result<Vec<markup::MarkupNode>> {
+    markup::select(&html, &css)
+}
+
+#[op2]
+#[string]
+fn op_crypto_digest(
+    #[string] algorithm: String,
+    #[string] data: String,
+    #[string] data_encoding: String,
+    #[string] encoding: String,
+) -> anyhow::Result<String> {
+    let data = crypto::decode(&data, &data_encoding)?;
+    crypto::encode(&crypto::digest(&algorithm, &data)?, &encoding)
+}
+
+#[op2]
+#[string]
+fn op_crypto_hmac(
+    #[string] algorithm: String,
+    #[string] key: String,
+    #[string] key_encoding: String,
+    #[string] data: String,
+    #[string] data_encoding: String,
+    #[string] encoding: String,
+) -> anyhow::Result<String> {
+    let key = crypto::decode(&key, &key_encoding)?;
+    let data = crypto::decode(&data, &data_encoding)?;
+    crypto::encode(&crypto::hmac(&algorithm, &key, &data)?, &encoding)
+}
+
+#[op2]
+#[string]
+fn op_crypto_encode(
+    #[string] data: String,
+    #[string] data_encoding: String,
+    #[string] encoding: String,
+) -> anyhow::Result<String> {
+    crypto::convert(&data, &data_encoding, &encoding)
+}
+
+#[op2]
+#[string]
+fn op_crypto_decode(
+    #[string] data: String,
+    #[string] encoding: String,
+    #[string] output_encoding: String,
+) -> anyhow::Result<String> {
+    crypto::convert(&data, &encoding, &output_encoding)
+}
+
+// keys, ivs, ciphertexts and signatures cross the op boundary as base64
+#[op2]
+#[string]
+fn op_crypto_aes_encrypt(
+    #[string] mode: String,
+    #[string] key: String,
+    #[string] iv: String,
+    #[string] data: String,
+    #[string] data_encoding: String,
+) -> anyhow::Result<String> {
+    let encrypted = crypto::aes_encrypt(
+        &mode,
+        &crypto::decode(&key, "base64")?,
+        &crypto::decode(&iv, "base64")?,
+        &crypto::decode(&data, &data_encoding)?,
+    )?;
+    crypto::encode(&encrypted, "base64")
+}
+
+#[op2]
+#[string]
+fn op_crypto_aes_decrypt(
+    #[string] mode: String,
+    #[string] key: String,
+    #[string] iv: String,
+    #[string] data: String,
+    #[string] encoding: String,
+) -> anyhow::Result<String> {
+    let decrypted = crypto::aes_decrypt(
+        &mode,
+        &crypto::decode(&key, "base64")?,
+        &crypto::decode(&iv, "base64")?,
+        &crypto::decode(&data, "base64")?,
+    )?;
+    crypto::encode(&decrypted, &encoding)
+}
+
+#[op2]
+#[string]
+fn op_crypto_rsa_sign(
+    #[string] algorithm: String,
+    #[string] private_key: String,
+    #[string] data: String,
+    #[string] data_encoding: String,
+) -> anyhow::Result<String> {
+    crypto::encode(
+        &crypto::rsa_sign(&algorithm, &private_key, &crypto::decode(&data, &data_encoding)?)?,
+        "base64",
+    )
+}
+
+#[op2(fast)]
+fn op_crypto_rsa_verify(
+    #[string] algorithm: String,
+    #[string] public_key: String,
+    #[string] data: String,
+    #[string] data_encoding: String,
+    #[string] signature: String,
+) -> anyhow::Result<bool> {
+    crypto::rsa_verify(
+        &algorithm,
+        &public_key,
+        &crypto::decode(&data, &data_encoding)?,
+        &crypto::decode(&signature, "base64")?,
+    )
+}
+
+#[op2]
+#[string]
+fn op_crypto_random_bytes(length: u32, #[string] encoding: String) -> anyhow::Result<String> {
+    crypto::encode(&crypto::random_bytes(length as usize), &encoding)
+}
+
+#[op2]
+#[string]
+fn op_crypto_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[op2]
+#[string]
+fn op_faker(#[string] name: String, #[serde] args: Vec<String>) -> anyhow::Result<String> {
+    let function = EnvFunction::from_str(name.as_str())
+        .map_err(|_| Error::msg(format!("unknown faker `{}`", name)))?;
+    get_env_result(function, &args)
+}
+
+#[op2]
+#[serde]
+fn op_validate_schema(
+    state: &mut OpState,
+    #[serde] instance: serde_json::Value,
+    #[serde] schema: serde_json::Value,
+) -> anyhow::Result<schema::SchemaValidation> {
+    let context = state
+        .try_borrow_mut::<Context>()
+        .ok_or(Error::msg("context is none"))?;
+    // without a schema the record's openapi operation response is used
+    let schema = match schema {
+        serde_json::Value::Null | serde_json::Value::String(_) => {
+            let operation_id = schema
+                .as_str()
+                .map(|s| s.to_string())
+                .or(context.operation_id.clone())
+                .ok_or(Error::msg("request has no openapi operation id"))?;
+            let openapi = context
+                .openapi
+                .clone()
+                .ok_or(Error::msg("collection has no openapi spec"))?;
+            schema::openapi_response_schema(&openapi, &operation_id, context.response.status)?
+        }
+        _ => schema,
+    };
+    let validation = schema::validate(&instance, &schema)?;
+    for error in validation.errors.iter() {
+        context.logger.add_warn(
+            context.scope_name.clone(),
+            format!("schema error at `{}`: {}", error.instance_path, error.message),
+        );
+    }
+    Ok(validation)
+}
+
 #[op2(fast)]
 fn op_test_skip()-> anyhow::Result<()> {
     Err(Error::from(SkipError {}))
@@ -597,3 +1658,211 @@ impl ScriptTree {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(scope: &str, script: &str) -> ScriptScope {
+        ScriptScope {
+            script: script.to_string(),
+            scope: scope.to_string(),
+        }
+    }
+
+    async fn run(scripts: Vec<ScriptScope>, context: Context) -> Context {
+        ScriptRuntime::run_async(scripts, context, CancellationToken::new())
+            .await
+            .unwrap()
+    }
+
+    fn env(context: &Context, key: &str) -> String {
+        context
+            .envs
+            .get(key)
+            .map(|v| v.value.clone())
+            .unwrap_or_default()
+    }
+
+    // `a` lives in every layer, the topmost wins as in `EnvironmentConfig::layer_over`.
+    fn scoped_context() -> Context {
+        let mut context = Context {
+            environment: Some("dev".to_string()),
+            collection: Some("Pets".to_string()),
+            ..Default::default()
+        };
+        for (key, scope) in [
+            ("g", ENVIRONMENT_GLOBALS),
+            ("e", "dev"),
+            ("c", "Pets Collection"),
+            ("a", "Pets Collection"),
+        ] {
+            context.envs.insert(
+                key.to_string(),
+                EnvironmentItemValue {
+                    value: key.to_string(),
+                    scope: scope.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        context
+    }
+
+    #[tokio::test]
+    async fn set_variable_uses_the_real_scope_names() {
+        let script = "netpurr.globals.set(\"g1\", 1);\
+                      netpurr.environment.set(\"e1\", 2);\
+                      netpurr.collection_variables.set(\"c1\", 3);";
+        let context = run(vec![scope("request", script)], scoped_context()).await;
+        assert_eq!(context.envs["g1"].scope, ENVIRONMENT_GLOBALS);
+        assert_eq!(context.envs["e1"].scope, "dev");
+        assert_eq!(context.envs["c1"].scope, "Pets Collection");
+        assert_eq!(context.env_changes.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn unset_variable_only_removes_its_own_scope() {
+        let script = "netpurr.environment.unset(\"g\");\
+                      netpurr.environment.unset(\"e\");\
+                      netpurr.globals.unset(\"a\");\
+                      netpurr.collection_variables.unset(\"c\");";
+        let context = run(vec![scope("request", script)], scoped_context()).await;
+        assert!(context.envs.contains_key("g"));
+        assert!(!context.envs.contains_key("e"));
+        assert!(context.envs.contains_key("a"));
+        assert!(!context.envs.contains_key("c"));
+    }
+
+    #[tokio::test]
+    async fn clear_variables_per_scope() {
+        let context = run(
+            vec![scope("request", "netpurr.collection_variables.clear();")],
+            scoped_context(),
+        )
+        .await;
+        assert_eq!(
+            context.envs.keys().cloned().collect::<Vec<String>>(),
+            vec!["e", "g"]
+        );
+        let context = run(
+            vec![scope("request", "netpurr.globals.clear(); netpurr.environment.clear();")],
+            scoped_context(),
+        )
+        .await;
+        assert_eq!(
+            context.envs.keys().cloned().collect::<Vec<String>>(),
+            vec!["a", "c"]
+        );
+    }
+
+    #[tokio::test]
+    async fn pm_variables_only_see_their_own_scope() {
+        let script = "netpurr.set_env(\"env_e\", String(pm.environment.get(\"e\")));\
+                      netpurr.set_env(\"env_c\", String(pm.environment.get(\"c\")));\
+                      netpurr.set_env(\"has_c\", String(pm.collectionVariables.has(\"c\")));\
+                      netpurr.set_env(\"has_g\", String(pm.collectionVariables.has(\"g\")));\
+                      pm.globals.clear();";
+        let context = run(vec![scope("request", script)], scoped_context()).await;
+        assert_eq!(env(&context, "env_e"), "e");
+        assert_eq!(env(&context, "env_c"), "undefined");
+        assert_eq!(env(&context, "has_c"), "true");
+        assert_eq!(env(&context, "has_g"), "false");
+        assert!(!context.envs.contains_key("g"));
+        assert!(context.envs.contains_key("e"));
+    }
+
+    #[test]
+    fn truncated_response_keeps_the_preview_and_the_file() {
+        let response = http::Response {
+            body: Arc::new(http::HttpBody {
+                base64: general_purpose::STANDARD.encode("preview"),
+                size: 2_000_000,
+                response_file: "/missing/response".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let js_response = JsResponse::from_data_response(response);
+        assert_eq!(js_response.text, "preview");
+        assert_eq!(js_response.size, 2_000_000);
+        assert!(js_response.truncated);
+        assert_eq!(js_response.response_file, "/missing/response");
+    }
+
+    #[tokio::test]
+    async fn setting_a_secret_keeps_it_secret() {
+        let mut context = Context::default();
+        context.envs.insert(
+            "token".to_string(),
+            EnvironmentItemValue {
+                value: "old".to_string(),
+                value_type: EnvironmentValueType::Secret,
+                ..Default::default()
+            },
+        );
+        let script = "netpurr.set_env(\"token\", \"new\"); netpurr.globals.set(\"plain\", 1);";
+        let context = run(vec![scope("request", script)], context).await;
+        assert_eq!(context.envs["token"].value, "new");
+        assert_eq!(context.envs["token"].value_type, EnvironmentValueType::Secret);
+        assert_eq!(context.envs["plain"].value_type, EnvironmentValueType::String);
+    }
+
+    #[test]
+    fn encoded_response_text_is_decoded() {
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, b"{\"a\":1}").unwrap();
+        let gzip = encoder.finish().unwrap();
+        let mut body = http::HttpBody::new(gzip.clone());
+        body.content_encoding = ContentEncoding::GZIP;
+        let response = http::Response {
+            body: Arc::new(body),
+            ..Default::default()
+        };
+        let js_response = JsResponse::from_data_response(response);
+        assert_eq!(js_response.text, "{\"a\":1}");
+        assert_eq!(js_response.size, gzip.len());
+        assert_eq!(js_response.base64, general_purpose::STANDARD.encode(&gzip));
+    }
+
+    #[test]
+    fn non_utf8_response_text_is_lossy() {
+        let response = http::Response {
+            body: Arc::new(http::HttpBody::new(vec![b'o', b'k', 0xff])),
+            ..Default::default()
+        };
+        let js_response = JsResponse::from_data_response(response);
+        assert_eq!(js_response.text, "ok\u{fffd}");
+    }
+
+    #[test]
+    fn environment_scope_falls_back_to_globals() {
+        let context = Context::default();
+        assert_eq!(context.scope_name(&EnvironmentScope::Environment), ENVIRONMENT_GLOBALS);
+        assert_eq!(context.scope_name(&EnvironmentScope::Collection), "Collection");
+    }
+
+    #[tokio::test]
+    async fn globals_do_not_leak_between_scopes() {
+        let scripts = vec![
+            scope("folder", "globalThis.leak = 1; globalThis.assert = undefined;"),
+            scope(
+                "request",
+                "netpurr.set_env(\"leak\", typeof globalThis.leak);\
+                 netpurr.set_env(\"assert\", typeof assert);",
+            ),
+        ];
+        let context = run(scripts, Context::default()).await;
+        assert_eq!(env(&context, "leak"), "undefined");
+        assert_eq!(env(&context, "assert"), "function");
+    }
+
+    #[tokio::test]
+    async fn aes_keeps_binary_data() {
+        let script = "let key = \"AAAAAAAAAAAAAAAAAAAAAA==\", iv = \"AAAAAAAAAAAAAAAA\";\
+                      let data = netpurr.crypto.aes_gcm_encrypt(key, iv, \"ff0080\", \"hex\");\
+                      netpurr.set_env(\"plain\", netpurr.crypto.aes_gcm_decrypt(key, iv, data, \"hex\"));";
+        let context = run(vec![scope("request", script)], Context::default()).await;
+        assert_eq!(env(&context, "plain"), "ff0080");
+    }
+}
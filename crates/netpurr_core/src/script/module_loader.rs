@@ -0,0 +1,76 @@
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
+
+use anyhow::Error;
+use deno_core::url::Url;
+use deno_core::{
+    resolve_import, FsModuleLoader, ModuleLoader, ModuleSourceFuture, ModuleSpecifier,
+    ResolutionKind,
+};
+
+pub const LIB_PREFIX: &str = "lib:";
+
+/// Resolves `lib:name.js` imports from the workspace `scripts/` dir, other imports work
+/// like `FsModuleLoader`.
+pub struct LibModuleLoader {
+    lib_dir: Option<PathBuf>,
+    fs_module_loader: FsModuleLoader,
+}
+
+impl LibModuleLoader {
+    pub fn new(lib_dir: Option<PathBuf>) -> Self {
+        LibModuleLoader {
+            lib_dir,
+            fs_module_loader: FsModuleLoader,
+        }
+    }
+
+    fn resolve_lib(&self, name: &str) -> Result<ModuleSpecifier, Error> {
+        let lib_dir = self
+            .lib_dir
+            .clone()
+            .ok_or(Error::msg("workspace script library is not available"))?;
+        let relative_path = Path::new(name);
+        if relative_path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(Error::msg(format!(
+                "`{}{}` must be a path inside the script library",
+                LIB_PREFIX, name
+            )));
+        }
+        let path = lib_dir.join(relative_path);
+        if !path.is_file() {
+            return Err(Error::msg(format!(
+                "`{}{}` is not found in {:?}",
+                LIB_PREFIX, name, lib_dir
+            )));
+        }
+        Url::from_file_path(path).map_err(|_| Error::msg(format!("invalid lib path `{}`", name)))
+    }
+}
+
+impl ModuleLoader for LibModuleLoader {
+    fn resolve(
+        &self,
+        specifier: &str,
+        referrer: &str,
+        _kind: ResolutionKind,
+    ) -> Result<ModuleSpecifier, Error> {
+        match specifier.strip_prefix(LIB_PREFIX) {
+            Some(name) => self.resolve_lib(name),
+            None => Ok(resolve_import(specifier, referrer)?),
+        }
+    }
+
+    fn load(
+        &self,
+        module_specifier: &ModuleSpecifier,
+        maybe_referrer: Option<&ModuleSpecifier>,
+        is_dyn_import: bool,
+    ) -> Pin<Box<ModuleSourceFuture>> {
+        self.fs_module_loader
+            .load(module_specifier, maybe_referrer, is_dyn_import)
+    }
+}
@@ -9,15 +9,104 @@
         await core.ops.op_sleep(time);
     }
 
-    globalThis.fetch = async function (request) {
-        let response = await core.ops.op_http_fetch(request);
+    function toHeaderList(headers) {
+        if (headers === undefined || headers === null) {
+            return [];
+        }
+        if (Array.isArray(headers)) {
+            return headers.map((h) => Array.isArray(h) ? {name: String(h[0]), value: String(h[1])} : {name: String(h.name), value: String(h.value)});
+        }
+        return Object.entries(headers).map(([name, value]) => ({name, value: String(value)}));
+    }
+
+    function hasHeader(headers, name) {
+        return headers.some((h) => h.name.toLowerCase() === name);
+    }
+
+    function first(nodes) {
+        return Array.isArray(nodes) ? nodes[0] : undefined;
+    }
+
+    const BASE64_ALPHABET = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    function base64ToBytes(base64) {
+        let clean = base64.replace(/=+$/, "");
+        let bytes = new Uint8Array(Math.floor(clean.length * 3 / 4));
+        let buffer = 0, bits = 0, index = 0;
+        for (const char of clean) {
+            buffer = (buffer << 6) | BASE64_ALPHABET.indexOf(char);
+            bits += 6;
+            if (bits >= 8) {
+                bits -= 8;
+                bytes[index++] = (buffer >> bits) & 0xff;
+            }
+        }
+        return bytes;
+    }
+
+    // Adds the body readers: `json`, `bytes()`, `xml(namespaces).select(xpath)` and
+    // `html().select(css)`, and a case-insensitive `header(name)`.
+    function withBody(response) {
         try {
             response.json = JSON.parse(response.text);
         } catch (e) {
         }
+        response.header = (name) => {
+            let header = response.headers.find((h) => h.name.toLowerCase() === String(name).toLowerCase());
+            return header === undefined ? undefined : header.value;
+        };
+        response.bytes = () => base64ToBytes(response.base64);
+        response.xml = (namespaces) => {
+            let select = (xpath) => core.ops.op_xml_xpath(response.text, xpath, namespaces === undefined ? {} : namespaces);
+            return {
+                select,
+                text: (xpath) => {
+                    let result = select(xpath);
+                    return Array.isArray(result) ? (first(result) || {}).text : result;
+                },
+                attr: (xpath, name) => (first(select(xpath)) || {attributes: {}}).attributes[name],
+            }
+        };
+        response.html = () => {
+            let select = (css) => core.ops.op_html_select(response.text, css);
+            return {
+                select,
+                text: (css) => (first(select(css)) || {}).text,
+                attr: (css, name) => (first(select(css)) || {attributes: {}}).attributes[name],
+            }
+        };
         return response
     }
 
+    // fetch(url, init) or fetch({url, ...init}), the body may be a string, a JSON value or `init.form`
+    globalThis.fetch = async function (input, init) {
+        let options = typeof input === "string" ? Object.assign({url: input}, init) : Object.assign({}, input, init);
+        let headers = toHeaderList(options.headers);
+        let body = "";
+        if (options.form !== undefined) {
+            body = Object.entries(options.form)
+                .map(([key, value]) => `${encodeURIComponent(key)}=${encodeURIComponent(String(value))}`)
+                .join("&");
+            if (!hasHeader(headers, "content-type")) {
+                headers.push({name: "Content-Type", value: "application/x-www-form-urlencoded"});
+            }
+        } else if (options.json !== undefined || (options.body !== undefined && options.body !== null && typeof options.body !== "string")) {
+            body = JSON.stringify(options.json !== undefined ? options.json : options.body);
+            if (!hasHeader(headers, "content-type")) {
+                headers.push({name: "Content-Type", value: "application/json"});
+            }
+        } else if (typeof options.body === "string") {
+            body = options.body;
+        }
+        let request = {
+            method: options.method === undefined ? "GET" : String(options.method),
+            url: String(options.url),
+            headers,
+            body,
+        };
+        return withBody(await core.ops.op_http_fetch(request));
+    }
+
     globalThis.assert = function (expect, actual) {
         if (core.ops.op_equal(expect, actual)) {
             core.ops.op_append_assert(true, `Expect equal actual is "${expect}"`);
@@ -25,6 +114,121 @@
             core.ops.op_append_assert(false, `Expect is "${expect}" but actual is "${actual}"`);
         }
     }
+    const isObject = (value) => typeof value === "object" && value !== null;
+    class Assertion {
+        constructor(actual) {
+            this._actual = actual;
+            this._negate = false;
+            this._deep = false;
+        }
+
+        _assert(assertion, expected) {
+            core.ops.op_expect(assertion, this._actual, expected === undefined ? null : expected, this._negate);
+            return this;
+        }
+
+        get not() {
+            this._negate = !this._negate;
+            return this;
+        }
+
+        get deep() {
+            this._deep = true;
+            return this;
+        }
+
+        equal(expected) {
+            if (this._deep) {
+                return this._assert("deep.equal", expected);
+            }
+            // objects lose their identity once they cross the op boundary, so compare them here
+            if (isObject(this._actual) || isObject(expected)) {
+                return this._assert("strict.equal", {same: this._actual === expected, value: expected === undefined ? null : expected});
+            }
+            return this._assert("equal", expected);
+        }
+
+        eql(expected) {
+            return this._assert("deep.equal", expected);
+        }
+
+        include(expected) {
+            return this._assert("include", expected);
+        }
+
+        match(regexp) {
+            // run the RegExp here so lookaround and backreferences keep their JS meaning
+            let pattern;
+            try {
+                pattern = regexp instanceof RegExp ? new RegExp(regexp.source, regexp.flags.replace(/[gy]/g, "")) : new RegExp(String(regexp));
+            } catch (e) {
+                return this._assert("match", {matched: false, pattern: `/${regexp}/`, error: String(e)});
+            }
+            let matched = typeof this._actual === "string" && pattern.test(this._actual);
+            return this._assert("match", {matched: matched, pattern: String(pattern)});
+        }
+
+        property(name, value) {
+            return this._assert("property", {name: String(name), value: value === undefined ? null : value, has_value: arguments.length > 1});
+        }
+
+        above(n) {
+            return this._assert("above", n);
+        }
+
+        below(n) {
+            return this._assert("below", n);
+        }
+
+        least(n) {
+            return this._assert("least", n);
+        }
+
+        most(n) {
+            return this._assert("most", n);
+        }
+
+        lengthOf(n) {
+            return this._assert("lengthOf", n);
+        }
+
+        oneOf(list) {
+            return this._assert("oneOf", list);
+        }
+
+        a(type) {
+            return this._assert("a", type);
+        }
+    }
+    Assertion.prototype.eq = Assertion.prototype.equal;
+    Assertion.prototype.equals = Assertion.prototype.equal;
+    Assertion.prototype.includes = Assertion.prototype.include;
+    Assertion.prototype.contain = Assertion.prototype.include;
+    Assertion.prototype.contains = Assertion.prototype.include;
+    Assertion.prototype.gt = Assertion.prototype.above;
+    Assertion.prototype.lt = Assertion.prototype.below;
+    Assertion.prototype.gte = Assertion.prototype.least;
+    Assertion.prototype.lte = Assertion.prototype.most;
+    Assertion.prototype.an = Assertion.prototype.a;
+    for (const [name, assertion, expected] of [["true", "equal", true], ["false", "equal", false], ["null", "equal", null], ["undefined", "equal", null], ["ok", "ok"], ["empty", "empty"], ["exist", "exist"]]) {
+        Object.defineProperty(Assertion.prototype, name, {
+            get() {
+                return this._assert(assertion, expected);
+            }
+        });
+    }
+    for (const chain of ["to", "be", "been", "is", "that", "which", "and", "has", "have", "with", "at", "of", "same", "does", "still"]) {
+        Object.defineProperty(Assertion.prototype, chain, {
+            get() {
+                return this;
+            }
+        });
+    }
+
+    globalThis.expect = function (actual) {
+        return new Assertion(actual);
+    }
+
     globalThis.assert_nlp = function (expect, actual, expect_similarity) {
         let actual_similarity = core.ops.op_nlp_similarity(expect, actual);
         if (actual_similarity > expect_similarity) {
@@ -63,7 +267,182 @@
             return core.ops.op_nlp_similarity(a, b)
         }
     }
+    function findValue(list, key, ignoreCase) {
+        let item = list.filter((i) => i.enable && (ignoreCase ? i.key.toLowerCase() === key.toLowerCase() : i.key === key)).pop();
+        return item === undefined ? undefined : item.value;
+    }
+
+    function toObject(list) {
+        let object = {};
+        list.filter((i) => i.enable).forEach((i) => object[i.key] = i.value);
+        return object;
+    }
+
+    function formOf(request) {
+        return request.body.body_type === "FROM_DATA" ? request.body.body_form_data : request.body.body_xxx_form;
+    }
+
+    const request = {
+        get method() {
+            return core.ops.op_request().method;
+        },
+        set method(value) {
+            core.ops.op_set_request_method(String(value));
+        },
+        get url() {
+            return core.ops.op_request().raw_url;
+        },
+        set url(value) {
+            core.ops.op_set_request_url(String(value));
+        },
+        get body() {
+            return core.ops.op_request().body.body_str;
+        },
+        set body(value) {
+            core.ops.op_set_request_body(typeof value === "string" ? value : JSON.stringify(value));
+        },
+        get auth() {
+            return core.ops.op_request().auth;
+        },
+        set auth(value) {
+            core.ops.op_set_request_auth(value);
+        },
+        headers: {
+            get: (key) => findValue(core.ops.op_request().headers, key, true),
+            set: (key, value) => core.ops.op_set_request_header(key, String(value)),
+            remove: (key) => core.ops.op_remove_request_header(key),
+            all: () => toObject(core.ops.op_request().headers),
+        },
+        params: {
+            get: (key) => findValue(core.ops.op_request().params, key, false),
+            set: (key, value) => core.ops.op_set_request_param(key, String(value)),
+            remove: (key) => core.ops.op_remove_request_param(key),
+            all: () => toObject(core.ops.op_request().params),
+        },
+        path_variables: {
+            get: (key) => {
+                let variable = core.ops.op_request().path_variables.find((p) => p.key === key);
+                return variable === undefined ? undefined : variable.value;
+            },
+            set: (key, value) => core.ops.op_set_request_path_variable(key, String(value)),
+        },
+        form: {
+            get: (key) => findValue(formOf(core.ops.op_request()), key, false),
+            set: (key, value) => core.ops.op_set_request_form(key, String(value)),
+            remove: (key) => core.ops.op_remove_request_form(key),
+            all: () => toObject(formOf(core.ops.op_request())),
+        },
+        bearer: (token) => {
+            core.ops.op_set_request_auth({auth_type: "BearerToken", bearer_token: String(token)});
+        },
+        basic: (username, password) => {
+            core.ops.op_set_request_auth({auth_type: "BasicAuth", basic_username: String(username), basic_password: String(password)});
+        },
+        no_auth: () => {
+            core.ops.op_set_request_auth({auth_type: "NoAuth"});
+        },
+    }
+
+    // Bytes cross the op boundary as hex. Inputs are utf8, hex or base64 text or a Uint8Array,
+    // outputs are text in `encoding` or a Uint8Array for "bytes".
+    const toHex = (bytes) => Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+    const fromHex = (hex) => Uint8Array.from(hex.match(/../g) ?? [], (b) => parseInt(b, 16));
+    const cryptoInput = (data, encoding) => data instanceof Uint8Array ? [toHex(data), "hex"] : [String(data), encoding];
+    const cryptoOutput = (encoding, op) => encoding === "bytes" ? fromHex(op("hex")) : op(encoding);
+    const digest = (algorithm) => (data, encoding = "hex", data_encoding = "utf8") => {
+        let [input, input_encoding] = cryptoInput(data, data_encoding);
+        return cryptoOutput(encoding, (output) => core.ops.op_crypto_digest(algorithm, input, input_encoding, output));
+    };
+    const hmac = (algorithm) => (key, data, encoding = "hex", key_encoding = "utf8", data_encoding = "utf8") => {
+        let [key_input, key_input_encoding] = cryptoInput(key, key_encoding);
+        let [input, input_encoding] = cryptoInput(data, data_encoding);
+        return cryptoOutput(encoding, (output) => core.ops.op_crypto_hmac(algorithm, key_input, key_input_encoding, input, input_encoding, output));
+    };
+    const encoder = (encoding) => (data, data_encoding = "utf8") => {
+        let [input, input_encoding] = cryptoInput(data, data_encoding);
+        return core.ops.op_crypto_encode(input, input_encoding, encoding);
+    };
+    const decoder = (encoding) => (data, output_encoding = "utf8") =>
+        cryptoOutput(output_encoding, (output) => core.ops.op_crypto_decode(String(data), encoding, output));
+    const aesEncrypt = (mode) => (key, iv, data, data_encoding = "utf8") => {
+        let [input, input_encoding] = cryptoInput(data, data_encoding);
+        return core.ops.op_crypto_aes_encrypt(mode, key, iv, input, input_encoding);
+    };
+    const aesDecrypt = (mode) => (key, iv, data, encoding = "utf8") =>
+        cryptoOutput(encoding, (output) => core.ops.op_crypto_aes_decrypt(mode, key, iv, String(data), output));
+    const crypto = {
+        md5: digest("md5"),
+        sha1: digest("sha1"),
+        sha256: digest("sha256"),
+        sha512: digest("sha512"),
+        hmac_sha1: hmac("sha1"),
+        hmac_sha256: hmac("sha256"),
+        hmac_sha512: hmac("sha512"),
+        base64_encode: encoder("base64"),
+        base64_decode: decoder("base64"),
+        base64url_encode: encoder("base64url"),
+        base64url_decode: decoder("base64url"),
+        hex_encode: encoder("hex"),
+        hex_decode: decoder("hex"),
+        url_encode: encoder("url"),
+        url_decode: decoder("url"),
+        aes_gcm_encrypt: aesEncrypt("gcm"),
+        aes_gcm_decrypt: aesDecrypt("gcm"),
+        aes_cbc_encrypt: aesEncrypt("cbc"),
+        aes_cbc_decrypt: aesDecrypt("cbc"),
+        rsa_sign: (private_key, data, algorithm = "RS256", data_encoding = "utf8") => {
+            let [input, input_encoding] = cryptoInput(data, data_encoding);
+            return core.ops.op_crypto_rsa_sign(algorithm, private_key, input, input_encoding);
+        },
+        rsa_verify: (public_key, data, signature, algorithm = "RS256", data_encoding = "utf8") => {
+            let [input, input_encoding] = cryptoInput(data, data_encoding);
+            return core.ops.op_crypto_rsa_verify(algorithm, public_key, input, input_encoding, signature);
+        },
+        random_bytes: (length, encoding = "hex") => core.ops.op_crypto_random_bytes(length, encoding),
+        uuid: () => core.ops.op_crypto_uuid(),
+    }
+
+    // The dynamic variable generators behind `{{$randomInt(1,100)}}` and friends.
+    function fake(name, ...args) {
+        return core.ops.op_faker(name, args.filter((a) => a !== undefined).map(String));
+    }
+
+    const faker = {
+        random_int: (min, max) => Number(fake("RandomInt", min, max)),
+        uuid: () => fake("UUID"),
+        timestamp: () => Number(fake("Timestamp")),
+        iso_timestamp: () => fake("IsoTimestamp"),
+        random_email: () => fake("RandomEmail"),
+        random_first_name: () => fake("RandomFirstName"),
+        random_last_name: () => fake("RandomLastName"),
+        random_full_name: () => fake("RandomFullName"),
+        random_phone: () => fake("RandomPhone"),
+        random_ip: () => fake("RandomIP"),
+        random_lorem: (words) => fake("RandomLorem", words),
+        future_date: (days) => fake("FutureDate", days),
+        past_date: (days) => fake("PastDate", days),
+        random_alpha_numeric: (length) => fake("RandomAlphaNumeric", length),
+        random_from: (...values) => fake("RandomFrom", ...values),
+        counter: (name) => Number(fake("Counter", name)),
+        generate: fake,
+    }
+
+    function variables(scope) {
+        return {
+            get: (key) => core.ops.op_get_env(key),
+            set: (key, value) => core.ops.op_set_variable(scope, key, String(value)),
+            unset: (key) => core.ops.op_unset_variable(scope, key),
+            clear: () => core.ops.op_clear_variables(scope),
+        }
+    }
+
     globalThis.netpurr = {
+        request,
+        crypto,
+        faker,
+        environment: variables("Environment"),
+        globals: variables("Globals"),
+        collection_variables: variables("Collection"),
         get_testcase: () => {
             return JSON.parse(core.ops.op_get_testcase());
         },
@@ -92,12 +471,7 @@
             return JSON.parse(json_value)
         },
         resp: () => {
-            let response = core.ops.op_response();
-            try {
-                response.json = JSON.parse(response.text);
-            } catch (e) {
-            }
-            return response
+            return withBody(core.ops.op_response());
         },
         test: (name, func) => {
             core.ops.op_open_test(name);
@@ -106,7 +480,214 @@
         },
         test_skip: () => {
             core.ops.op_test_skip();
+        },
+        validate_schema: (json, schema) => {
+            return core.ops.op_validate_schema(json === undefined ? null : json, schema === undefined ? null : schema);
+        }
+    }
+
+    // Postman `pm.*` compatibility, so imported Postman scripts run on top of the netpurr API.
+    function pmList(list) {
+        return {
+            get: (key) => list.get(key),
+            has: (key) => list.get(key) !== undefined,
+            add: (item) => list.set(item.key, item.value),
+            upsert: (item) => list.set(item.key, item.value),
+            remove: (key) => list.remove(typeof key === "string" ? key : key.key),
+            toObject: () => list.all(),
+            each: (func) => Object.entries(list.all()).forEach(([key, value]) => func({key, value})),
+        }
+    }
+
+    function pmVariables(scope) {
+        let scoped = variables(scope);
+        return {
+            get: (key) => core.ops.op_get_variable(scope, key) ?? undefined,
+            has: (key) => core.ops.op_get_variable(scope, key) != null,
+            set: (key, value) => scoped.set(key, value),
+            unset: (key) => scoped.unset(key),
+            clear: () => scoped.clear(),
+        }
+    }
+
+    function pmResponseOf(response) {
+        let headers = response.headers.map((h) => ({key: h.name, value: h.value}));
+        let header = (key) => {
+            let item = headers.filter((h) => h.key.toLowerCase() === String(key).toLowerCase()).pop();
+            return item === undefined ? undefined : item.value;
+        };
+        let isJson = () => {
+            try {
+                JSON.parse(response.text);
+                return true;
+            } catch (e) {
+                return false;
+            }
+        };
+        let assertCode = (result, expected) => {
+            core.ops.op_append_assert(result, `expected response to ${expected}, got ${response.status}`);
+        };
+        let pmResponse = {
+            code: response.status,
+            responseTime: response.elapsed_time,
+            responseSize: response.size,
+            cookies: {
+                get: (name) => (response.cookies.find((c) => c.name === name) || {}).value,
+                has: (name) => response.cookies.some((c) => c.name === name),
+                toObject: () => Object.fromEntries(response.cookies.map((c) => [c.name, c.value])),
+            },
+            headers: {
+                get: header,
+                has: (key) => header(key) !== undefined,
+                toObject: () => Object.fromEntries(headers.map((h) => [h.key, h.value])),
+                each: (func) => headers.forEach(func),
+            },
+            text: () => response.text,
+            json: () => JSON.parse(response.text),
+            to: {},
+        };
+        pmResponse.to.have = {
+            status: (code) => assertCode(response.status === code, `have status ${code}`),
+            header: (key, value) => {
+                let actual = header(key);
+                let result = value === undefined ? actual !== undefined : actual === String(value);
+                core.ops.op_append_assert(result, value === undefined
+                    ? `expected response to have header ${key}`
+                    : `expected response header ${key} to be "${value}" but got "${actual}"`);
+            },
+            body: (text) => expect(response.text).to.equal(text),
+            jsonBody: () => core.ops.op_append_assert(isJson(), "expected response to have a json body"),
+        };
+        pmResponse.to.be = {
+            get ok() {
+                return assertCode(response.status >= 200 && response.status < 300, "be ok");
+            },
+            get success() {
+                return assertCode(response.status >= 200 && response.status < 300, "be success");
+            },
+            get clientError() {
+                return assertCode(response.status >= 400 && response.status < 500, "be a client error");
+            },
+            get serverError() {
+                return assertCode(response.status >= 500 && response.status < 600, "be a server error");
+            },
+            get error() {
+                return assertCode(response.status >= 400 && response.status < 600, "be an error");
+            },
+            get json() {
+                return core.ops.op_append_assert(isJson(), "expected response to be json");
+            },
+        };
+        return pmResponse;
+    }
+
+    // pm.sendRequest accepts a url or a Postman request `{url, method, header, body: {mode, ...}}`
+    function pmFetchOptions(req) {
+        if (typeof req === "string") {
+            return {url: req};
+        }
+        let header = req.header === undefined ? req.headers : req.header;
+        let options = {
+            url: typeof req.url === "string" ? req.url : String(req.url && req.url.raw),
+            method: req.method === undefined ? "GET" : req.method,
+            headers: Array.isArray(header) ? header.map((h) => [h.key, h.value]) : header,
+        };
+        let body = req.body;
+        if (body !== undefined && body !== null) {
+            if (body.mode === "urlencoded" || body.mode === "formdata") {
+                options.form = Object.fromEntries((body[body.mode] || []).map((p) => [p.key, p.value]));
+            } else if (body.mode === "raw") {
+                options.body = String(body.raw);
+            } else {
+                options.body = body;
+            }
+        }
+        return options;
+    }
+
+    const pmRequest = {
+        get method() {
+            return request.method;
+        },
+        set method(value) {
+            request.method = value;
+        },
+        get url() {
+            return {
+                toString: () => request.url,
+                query: pmList(request.params),
+            };
+        },
+        set url(value) {
+            request.url = String(value);
+        },
+        headers: pmList(request.headers),
+        get body() {
+            let raw = request.body;
+            return {
+                mode: "raw",
+                raw,
+                toString: () => raw,
+                update: (value) => request.body = value,
+            };
+        },
+        set body(value) {
+            request.body = value;
+        },
+    }
+
+    const pmTest = (name, func) => {
+        core.ops.op_open_test(name);
+        try {
+            func();
+        } catch (e) {
+            core.ops.op_append_assert(false, String(e && e.message ? e.message : e));
         }
+        core.ops.op_close_test(name);
+    };
+    pmTest.skip = (name) => {
+        console.warn(`skip test ${name}`);
+    };
+
+    globalThis.pm = {
+        environment: pmVariables("Environment"),
+        globals: pmVariables("Globals"),
+        collectionVariables: pmVariables("Collection"),
+        variables: {
+            get: (key) => core.ops.op_has_env(key) ? core.ops.op_get_env(key) : undefined,
+            has: (key) => core.ops.op_has_env(key),
+            set: (key, value) => core.ops.op_set_env(key, String(value)),
+            replaceIn: (template) => String(template).replace(/\{\{([^{}]+)\}\}/g,
+                (matched, key) => core.ops.op_has_env(key) ? core.ops.op_get_env(key) : matched),
+        },
+        iterationData: {
+            get: (key) => netpurr.get_testcase()[key],
+            has: (key) => key in netpurr.get_testcase(),
+            toObject: () => netpurr.get_testcase(),
+        },
+        request: pmRequest,
+        get response() {
+            return pmResponseOf(core.ops.op_response());
+        },
+        test: pmTest,
+        expect: globalThis.expect,
+        sendRequest: (req, callback) => {
+            return fetch(pmFetchOptions(req)).then((response) => {
+                let pmResponse = pmResponseOf(response);
+                if (callback) {
+                    callback(null, pmResponse);
+                }
+                return pmResponse;
+            }, (e) => {
+                if (!callback) {
+                    throw e;
+                }
+                callback(e, null);
+            });
+        },
+        execution: {
+            skipRequest: () => core.ops.op_test_skip(),
+        },
     }
 
 })(globalThis);
\ No newline at end of file
@@ -0,0 +1,196 @@
+use anyhow::anyhow;
+use jsonschema::{Draft, JSONSchema};
+use openapiv3::{OpenAPI, ReferenceOr, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct SchemaValidation {
+    pub valid: bool,
+    pub errors: Vec<SchemaError>,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct SchemaError {
+    /// JSON pointer to the failing value in the validated document.
+    pub instance_path: String,
+    /// JSON pointer to the schema keyword that failed.
+    pub schema_path: String,
+    pub message: String,
+}
+
+pub fn validate(instance: &Value, schema: &Value) -> anyhow::Result<SchemaValidation> {
+    let compiled = JSONSchema::options()
+        .with_draft(draft_of(schema))
+        .compile(schema)
+        .map_err(|e| anyhow!("invalid schema at `{}`: {}", e.schema_path, e))?;
+    let errors: Vec<SchemaError> = match compiled.validate(instance) {
+        Ok(_) => vec![],
+        Err(errors) => errors
+            .map(|e| SchemaError {
+                instance_path: e.instance_path.to_string(),
+                schema_path: e.schema_path.to_string(),
+                message: e.to_string(),
+            })
+            .collect(),
+    };
+    Ok(SchemaValidation {
+        valid: errors.is_empty(),
+        errors,
+    })
+}
+
+/// Finds the JSON response schema of `operation_id` for `status` and turns it into a
+/// standalone JSON Schema, `#/components/...` references keep resolving against the spec.
+pub fn openapi_response_schema(
+    openapi: &OpenAPI,
+    operation_id: &str,
+    status: u16,
+) -> anyhow::Result<Value> {
+    let (_, _, operation) = openapi
+        .operations()
+        .find(|(_, _, op)| op.operation_id.as_deref() == Some(operation_id))
+        .ok_or(anyhow!("operation `{}` is not in the openapi spec", operation_id))?;
+    let responses = &operation.responses;
+    let response = responses
+        .responses
+        .get(&StatusCode::Code(status))
+        .or(responses.responses.get(&StatusCode::Range(status / 100)))
+        .or(responses.default.as_ref())
+        .ok_or(anyhow!(
+            "operation `{}` has no response for status {}",
+            operation_id,
+            status
+        ))?;
+    let response = resolve_response(openapi, response)?;
+    let media_type = response
+        .content
+        .iter()
+        .find(|(content_type, _)| content_type.contains("json"))
+        .map(|(_, media_type)| media_type)
+        .ok_or(anyhow!(
+            "operation `{}` has no json response for status {}",
+            operation_id,
+            status
+        ))?;
+    let schema = media_type
+        .schema
+        .as_ref()
+        .ok_or(anyhow!("operation `{}` response has no schema", operation_id))?;
+    let mut schema = match serde_json::to_value(schema)? {
+        Value::Object(map) => map,
+        other => {
+            let mut map = Map::new();
+            map.insert("allOf".to_string(), Value::Array(vec![other]));
+            map
+        }
+    };
+    if let Some(components) = &openapi.components {
+        schema.insert("components".to_string(), serde_json::to_value(components)?);
+    }
+    let mut schema = Value::Object(schema);
+    convert_nullable(&mut schema);
+    Ok(schema)
+}
+
+fn resolve_response<'a>(
+    openapi: &'a OpenAPI,
+    response: &'a ReferenceOr<Response>,
+) -> anyhow::Result<&'a Response> {
+    match response {
+        ReferenceOr::Item(item) => Ok(item),
+        ReferenceOr::Reference { reference } => {
+            let name = reference.trim_start_matches("#/components/responses/");
+            openapi
+                .components
+                .as_ref()
+                .and_then(|c| c.responses.get(name))
+                .and_then(|r| r.as_item())
+                .ok_or(anyhow!("response `{}` is not found", reference))
+        }
+    }
+}
+
+// OpenAPI 3.0 marks optional nulls with `nullable`, JSON Schema needs it in `type`. Only schema
+// objects are walked, so a property or example that happens to be named `nullable` is kept.
+fn convert_nullable(schema: &mut Value) {
+    let Value::Object(map) = schema else {
+        return;
+    };
+    if map.get("nullable") == Some(&Value::Bool(true)) {
+        map.remove("nullable");
+        if let Some(Value::String(type_name)) = map.get("type").cloned() {
+            map.insert(
+                "type".to_string(),
+                Value::Array(vec![Value::String(type_name), Value::from("null")]),
+            );
+        }
+    }
+    for (key, value) in map.iter_mut() {
+        match key.as_str() {
+            "properties" | "patternProperties" | "definitions" | "$defs" => {
+                if let Value::Object(schemas) = value {
+                    schemas.values_mut().for_each(convert_nullable);
+                }
+            }
+            "items" | "prefixItems" | "additionalProperties" | "not" | "allOf" | "anyOf"
+            | "oneOf" => match value {
+                Value::Array(schemas) => schemas.iter_mut().for_each(convert_nullable),
+                _ => convert_nullable(value),
+            },
+            "components" => {
+                if let Some(Value::Object(schemas)) = value.get_mut("schemas") {
+                    schemas.values_mut().for_each(convert_nullable);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draft_of(schema: &Value) -> Draft {
+    let url = schema
+        .get("$schema")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if url.contains("2020-12") {
+        Draft::Draft202012
+    } else if url.contains("2019-09") {
+        Draft::Draft201909
+    } else if url.contains("draft-06") {
+        Draft::Draft6
+    } else if url.contains("draft-04") {
+        Draft::Draft4
+    } else {
+        Draft::Draft7
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn convert_nullable_only_touches_schema_objects() {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "nullable": {"type": "boolean"},
+                "name": {"type": "string", "nullable": true},
+                "age": {"type": "integer", "nullable": false},
+                "tags": {"type": "array", "items": {"type": "string", "nullable": true}}
+            },
+            "example": {"nullable": true},
+            "components": {"schemas": {"Pet": {"type": "object", "nullable": true}}}
+        });
+        convert_nullable(&mut schema);
+        assert_eq!(schema["properties"]["nullable"], json!({"type": "boolean"}));
+        assert_eq!(schema["properties"]["name"], json!({"type": ["string", "null"]}));
+        assert_eq!(schema["properties"]["age"]["nullable"], json!(false));
+        assert_eq!(schema["properties"]["tags"]["items"]["type"], json!(["string", "null"]));
+        assert_eq!(schema["example"], json!({"nullable": true}));
+        assert_eq!(schema["components"]["schemas"]["Pet"]["type"], json!(["object", "null"]));
+    }
+}
@@ -1,50 +1,243 @@
 use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
 use std::str::FromStr;
 
+use base64::engine::general_purpose;
+use base64::Engine;
 use regex::Regex;
+use serde_json::Value;
 
-use crate::data::environment::{EnvironmentItemValue, EnvironmentValueType};
+use crate::data::environment::{EnvironmentItemValue, EnvironmentValueType, FileEncoding};
 use crate::data::environment_function::{EnvFunction, get_env_result};
+use crate::data::logger::Logger;
+
+const MAX_VARIABLE_DEPTH: usize = 10;
+
+/// A `{{...}}` template that could not be resolved, it is left as is in the output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariableError {
+    pub template: String,
+    pub message: String,
+}
+
+impl Display for VariableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.template, self.message)
+    }
+}
 
 pub fn replace_variable(content: String, envs: BTreeMap<String, EnvironmentItemValue>) -> String {
+    resolve_variable(content.as_str(), &envs).0
+}
+
+/// Like `replace_variable`, logging the templates that could not be resolved.
+pub fn replace_variable_with_logger(
+    content: String,
+    envs: &BTreeMap<String, EnvironmentItemValue>,
+    logger: &mut Logger,
+) -> String {
+    let (result, errors) = resolve_variable(content.as_str(), envs);
+    for error in errors {
+        logger.add_error("Variable".to_string(), error.to_string());
+    }
+    result
+}
+
+/// Resolves every `{{key ?? default | filter}}` in `content`. Values holding templates are
+/// resolved recursively, up to `MAX_VARIABLE_DEPTH` levels and failing on cycles.
+pub fn resolve_variable(
+    content: &str,
+    envs: &BTreeMap<String, EnvironmentItemValue>,
+) -> (String, Vec<VariableError>) {
+    let mut errors = vec![];
+    let result = resolve_content(content, envs, &mut vec![], false, &mut errors);
+    (result, errors)
+}
+
+/// Checks the templates in `content` without running the dynamic variable functions.
+pub fn check_variable(
+    content: &str,
+    envs: &BTreeMap<String, EnvironmentItemValue>,
+) -> Vec<VariableError> {
+    let mut errors = vec![];
+    resolve_content(content, envs, &mut vec![], true, &mut errors);
+    errors
+}
+
+fn resolve_content(
+    content: &str,
+    envs: &BTreeMap<String, EnvironmentItemValue>,
+    stack: &mut Vec<String>,
+    dry_run: bool,
+    errors: &mut Vec<VariableError>,
+) -> String {
     let re = Regex::new(r"\{\{.*?}}").unwrap();
-    let mut result = content.clone();
-    loop {
-        let temp = result.clone();
-        let find = re.find_iter(temp.as_str()).next();
-        match find {
-            None => break,
-            Some(find_match) => {
-                let key = find_match
-                    .as_str()
-                    .trim_start_matches("{{")
-                    .trim_end_matches("}}")
-                    .trim_start()
-                    .trim_end();
-                let v = envs.get(key);
-                match v {
-                    None => result.replace_range(find_match.range(), "{UNKNOWN}"),
-                    Some(etv) => match etv.value_type {
-                        EnvironmentValueType::String => {
-                            result.replace_range(find_match.range(), etv.value.as_str())
-                        }
-                        EnvironmentValueType::Function => {
-                            let env_func = EnvFunction::from_str(etv.value.as_str());
-                            match env_func {
-                                Ok(f) => result
-                                    .replace_range(find_match.range(), get_env_result(f).as_str()),
-                                Err(_) => {
-                                    result.replace_range(find_match.range(), "{UNKNOWN}");
-                                }
-                            }
-                        }
-                    },
-                }
+    let mut result = String::new();
+    let mut start = 0;
+    for find_match in re.find_iter(content) {
+        result.push_str(&content[start..find_match.start()]);
+        let template = find_match.as_str();
+        let expression = template.trim_start_matches("{{").trim_end_matches("}}");
+        match resolve_expression(expression, envs, stack, dry_run, errors) {
+            Ok(value) => result.push_str(value.as_str()),
+            Err(message) => {
+                errors.push(VariableError {
+                    template: template.to_string(),
+                    message,
+                });
+                result.push_str(template);
             }
         }
+        start = find_match.end();
     }
+    result.push_str(&content[start..]);
     result
 }
+
+fn resolve_expression(
+    expression: &str,
+    envs: &BTreeMap<String, EnvironmentItemValue>,
+    stack: &mut Vec<String>,
+    dry_run: bool,
+    errors: &mut Vec<VariableError>,
+) -> Result<String, String> {
+    let mut parts = split_unquoted(expression, "|").into_iter();
+    let key_default = parts.next().unwrap_or_default();
+    let (key, default) = match find_unquoted(key_default, "??") {
+        None => (key_default.trim(), None),
+        Some(index) => {
+            let default = key_default[index + 2..].trim();
+            (
+                key_default[..index].trim(),
+                Some(default.trim_matches('"').trim_matches('\'').to_string()),
+            )
+        }
+    };
+    let mut value = match envs.get(key) {
+        Some(etv) => match etv.value_type {
+            EnvironmentValueType::String | EnvironmentValueType::Secret => {
+                if stack.iter().any(|k| k == key) {
+                    return Err(format!("cycle {} -> {}", stack.join(" -> "), key));
+                }
+                if stack.len() >= MAX_VARIABLE_DEPTH {
+                    return Err(format!("nested deeper than {} levels", MAX_VARIABLE_DEPTH));
+                }
+                stack.push(key.to_string());
+                let mut nested_errors = vec![];
+                let value =
+                    resolve_content(etv.value.as_str(), envs, stack, dry_run, &mut nested_errors);
+                stack.pop();
+                if let Some(error) = nested_errors.first() {
+                    return Err(error.message.clone());
+                }
+                value
+            }
+            EnvironmentValueType::Function => {
+                let function = EnvFunction::from_str(etv.value.as_str())
+                    .map_err(|_| format!("unknown function `{}`", etv.value))?;
+                call_function(function, vec![], dry_run)?
+            }
+            EnvironmentValueType::File => read_file(etv, dry_run)?,
+        },
+        None => match (EnvFunction::parse_call(key), default) {
+            (Some((function, args)), _) => call_function(function, args, dry_run)?,
+            (None, Some(default)) => resolve_content(default.as_str(), envs, stack, dry_run, errors),
+            (None, None) if stack.is_empty() => return Err(format!("unknown variable `{}`", key)),
+            (None, None) => {
+                return Err(format!("unknown variable `{}` in {}", key, stack.join(" -> ")))
+            }
+        },
+    };
+    for filter in parts {
+        value = apply_filter(filter.trim(), value)?;
+    }
+    Ok(value)
+}
+
+// Byte index of the first `separator` outside '..' or ".." quotes.
+fn find_unquoted(text: &str, separator: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in text.char_indices() {
+        match quote {
+            Some(q) if q == c => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if text[index..].starts_with(separator) => return Some(index),
+            None => {}
+        }
+    }
+    None
+}
+
+fn split_unquoted<'a>(text: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = vec![];
+    let mut rest = text;
+    while let Some(index) = find_unquoted(rest, separator) {
+        parts.push(&rest[..index]);
+        rest = &rest[index + separator.len()..];
+    }
+    parts.push(rest);
+    parts
+}
+
+fn read_file(value: &EnvironmentItemValue, dry_run: bool) -> Result<String, String> {
+    let path = Path::new(value.value.as_str());
+    if dry_run {
+        return match path.is_file() {
+            true => Ok(value.value.clone()),
+            false => Err(format!("file `{}` not found", value.value)),
+        };
+    }
+    let content =
+        fs::read(path).map_err(|e| format!("can not read file `{}`: {}", value.value, e))?;
+    match value.file_encoding {
+        FileEncoding::Text => String::from_utf8(content)
+            .map_err(|_| format!("file `{}` is not utf-8 text, use base64", value.value)),
+        FileEncoding::Base64 => Ok(general_purpose::STANDARD.encode(content)),
+    }
+}
+
+fn call_function(function: EnvFunction, args: Vec<String>, dry_run: bool) -> Result<String, String> {
+    if dry_run {
+        return Ok(format!("${}", function));
+    }
+    get_env_result(function, &args).map_err(|e| e.to_string())
+}
+
+fn apply_filter(filter: &str, value: String) -> Result<String, String> {
+    match filter {
+        "urlencode" => Ok(urlencoding::encode(value.as_str()).into_owned()),
+        "base64" => Ok(general_purpose::STANDARD.encode(value.as_bytes())),
+        "upper" => Ok(value.to_uppercase()),
+        "lower" => Ok(value.to_lowercase()),
+        "trim" => Ok(value.trim().to_string()),
+        "json" => Ok(Value::String(value).to_string()),
+        _ => Err(format!("unknown filter `{}`", filter)),
+    }
+}
+
+/// Like [replace_variable_with_logger], but file variables resolve to their path instead of
+/// their content, for multipart file parts.
+pub fn replace_file_path_variable_with_logger(
+    content: String,
+    envs: &BTreeMap<String, EnvironmentItemValue>,
+    logger: &mut Logger,
+) -> String {
+    let envs = envs
+        .iter()
+        .map(|(key, value)| {
+            let mut value = value.clone();
+            if value.value_type == EnvironmentValueType::File {
+                value.value_type = EnvironmentValueType::String;
+            }
+            (key.clone(), value)
+        })
+        .collect();
+    replace_variable_with_logger(content, &envs, logger)
+}
+
 pub fn build_copy_name(mut name: String, names: HashSet<String>) -> String {
     name = name
         .splitn(2, "Copy")
@@ -60,3 +253,164 @@ pub fn build_copy_name(mut name: String, names: HashSet<String>) -> String {
     }
     return new_name;
 }
+
+/// Query a json value with a JSONPath subset: `$`, `.key`, `['key']`, `[index]`, `*` and `[*]`.
+pub fn json_path_query(value: &Value, path: &str) -> Vec<Value> {
+    let mut tokens = vec![];
+    let mut chars = path.trim().trim_start_matches('$').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                let mut key = String::new();
+                while let Some(next) = chars.peek() {
+                    if *next == '.' || *next == '[' {
+                        break;
+                    }
+                    key.push(chars.next().unwrap());
+                }
+                if !key.is_empty() {
+                    tokens.push(key);
+                }
+            }
+            '[' => {
+                let mut key = String::new();
+                while let Some(next) = chars.next() {
+                    if next == ']' {
+                        break;
+                    }
+                    key.push(next);
+                }
+                tokens.push(key.trim().to_string());
+            }
+            _ => {
+                let mut key = c.to_string();
+                while let Some(next) = chars.peek() {
+                    if *next == '.' || *next == '[' {
+                        break;
+                    }
+                    key.push(chars.next().unwrap());
+                }
+                tokens.push(key);
+            }
+        }
+    }
+    let mut result = vec![value.clone()];
+    for token in tokens.iter() {
+        let mut next_result = vec![];
+        for v in result.iter() {
+            if token == "*" {
+                match v {
+                    Value::Array(array) => next_result.extend(array.iter().cloned()),
+                    Value::Object(object) => next_result.extend(object.values().cloned()),
+                    _ => {}
+                }
+            } else if let Ok(index) = token.parse::<usize>() {
+                if let Some(item) = v.get(index) {
+                    next_result.push(item.clone());
+                }
+            } else {
+                let key = token.trim_matches('\'').trim_matches('"');
+                if let Some(item) = v.get(key) {
+                    next_result.push(item.clone());
+                }
+            }
+        }
+        result = next_result;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envs(items: &[(&str, &str)]) -> BTreeMap<String, EnvironmentItemValue> {
+        items
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.to_string(),
+                    EnvironmentItemValue {
+                        value: value.to_string(),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resolves_nested_variables() {
+        let envs = envs(&[
+            ("host", "{{scheme}}://{{domain}}"),
+            ("scheme", "https"),
+            ("domain", "a.com"),
+        ]);
+        let (result, errors) = resolve_variable("{{host}}/path", &envs);
+        assert_eq!(result, "https://a.com/path");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn reports_cycles() {
+        let envs = envs(&[("a", "{{b}}"), ("b", "{{a}}")]);
+        let (result, errors) = resolve_variable("{{a}}", &envs);
+        assert_eq!(result, "{{a}}");
+        assert_eq!(errors[0].message, "cycle a -> b -> a");
+    }
+
+    #[test]
+    fn stops_at_the_depth_limit() {
+        let chain: Vec<(String, String)> = (1..=MAX_VARIABLE_DEPTH)
+            .map(|i| (format!("v{}", i), format!("{{{{v{}}}}}", i + 1)))
+            .collect();
+        let mut items: Vec<(&str, &str)> = chain
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        items.push(("v11", "end"));
+        let envs = envs(&items);
+        let (result, errors) = resolve_variable("{{v2}}", &envs);
+        assert_eq!(result, "end");
+        assert!(errors.is_empty());
+        let (result, errors) = resolve_variable("{{v1}}", &envs);
+        assert_eq!(result, "{{v1}}");
+        assert_eq!(
+            errors[0].message,
+            format!("nested deeper than {} levels", MAX_VARIABLE_DEPTH)
+        );
+    }
+
+    #[test]
+    fn uses_defaults_for_unknown_keys() {
+        let envs = envs(&[("name", "netpurr")]);
+        assert_eq!(resolve_variable("{{missing ?? 'x'}}", &envs).0, "x");
+        assert_eq!(resolve_variable("{{name ?? 'x'}}", &envs).0, "netpurr");
+    }
+
+    #[test]
+    fn applies_filters() {
+        let envs = envs(&[("name", " Net Purr ")]);
+        assert_eq!(resolve_variable("{{name | trim | upper}}", &envs).0, "NET PURR");
+        assert_eq!(resolve_variable("{{name|trim|urlencode}}", &envs).0, "Net%20Purr");
+        let (result, errors) = resolve_variable("{{name | reverse}}", &envs);
+        assert_eq!(result, "{{name | reverse}}");
+        assert_eq!(errors[0].message, "unknown filter `reverse`");
+    }
+
+    #[test]
+    fn ignores_separators_inside_quotes() {
+        let envs = envs(&[]);
+        assert_eq!(resolve_variable("{{missing ?? 'a|b'}}", &envs).0, "a|b");
+        assert_eq!(resolve_variable("{{missing ?? \"a??b\" | upper}}", &envs).0, "A??B");
+    }
+
+    #[test]
+    fn reports_unknown_keys() {
+        let envs = envs(&[("a", "{{b}}")]);
+        let (result, errors) = resolve_variable("x {{missing}} y", &envs);
+        assert_eq!(result, "x {{missing}} y");
+        assert_eq!(errors[0].message, "unknown variable `missing`");
+        assert_eq!(resolve_variable("{{a}}", &envs).1[0].message, "unknown variable `b` in a");
+    }
+}
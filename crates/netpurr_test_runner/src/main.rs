@@ -3,18 +3,20 @@ use std::ops::Deref;
 use std::process::exit;
 use std::rc::Rc;
 use std::sync::{Arc, RwLock};
-use std::time::Duration;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use futures_util::future::join_all;
-use reqwest::Client;
 
-use netpurr_core::data::collections::{CollectionFolder, CollectionFolderOnlyRead, Testcase};
+use netpurr_core::data::collections::{
+    Collection, CollectionFolder, CollectionFolderOnlyRead, Testcase,
+};
+use netpurr_core::data::lint;
 use netpurr_core::data::test::TestStatus;
 use netpurr_core::data::workspace_data::WorkspaceData;
 use netpurr_core::runner;
 use netpurr_core::runner::test::ResultTreeFolder;
-use netpurr_core::runner::TestGroupRunResults;
+use netpurr_core::runner::{HttpClients, TestGroupRunResults};
+use netpurr_core::script::ScriptLimits;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -23,51 +25,89 @@ struct Args {
     workspace_name: String,
     #[arg(short, long)]
     collection_name: String,
+    /// Overrides the workspace script timeout, 0 disables it
+    #[arg(long)]
+    script_timeout_ms: Option<u64>,
+    /// Overrides the workspace script heap limit, 0 disables it
+    #[arg(long)]
+    script_max_heap_mb: Option<usize>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Lists the variables the selected environment can not resolve, fails if there are any
+    Lint,
 }
 
 fn main() {
-    //let args = Args::parse();
-    let args=Args{
-        workspace_name: "aiproject".to_string(),
-        collection_name: "百炼".to_string(),
-    };
-    let client = Client::builder()
-        .trust_dns(true)
-        .tcp_nodelay(true)
-        .timeout(Duration::from_secs(60))
-        .build()
-        .unwrap_or_default();
+    let args = Args::parse();
+    let clients = HttpClients::new(None);
     let mut workspace_data = WorkspaceData::default();
     workspace_data.load_all(args.workspace_name.clone());
+    let mut script_limits = workspace_data.get_script_limits();
+    if let Some(timeout_ms) = args.script_timeout_ms {
+        script_limits.timeout_ms = timeout_ms;
+    }
+    if let Some(max_heap_mb) = args.script_max_heap_mb {
+        script_limits.max_heap_mb = max_heap_mb;
+    }
     let test_group_run_results = Arc::new(RwLock::new(TestGroupRunResults::default()));
     let collection_op = workspace_data.get_collection_by_name(args.collection_name.clone());
     match collection_op {
         None => {
-            println!("{}", "collection is not exist")
+            println!("{}", "collection is not exist");
+            exit(1);
+        }
+        Some(collection) if matches!(args.command, Some(Command::Lint)) => {
+            lint_collection(workspace_data, collection)
         }
         Some(collection) => run_test_group(
-            client,
+            clients,
             workspace_data,
             test_group_run_results,
-            args.collection_name.clone(),
             collection.folder.borrow().get_path(),
             None,
             collection.folder.clone(),
+            script_limits,
         ),
     }
 }
+fn lint_collection(workspace_data: WorkspaceData, collection: Collection) {
+    let envs = workspace_data.get_build_envs(Some(collection.clone()));
+    let report = lint::lint_folder(collection.folder.clone(), &envs);
+    let unresolved = report.unresolved();
+    for usage in unresolved.iter() {
+        println!(
+            "{} [{}] {}: {}",
+            usage.path,
+            usage.location,
+            usage.key,
+            usage.error.clone().unwrap_or_default()
+        );
+    }
+    if unresolved.is_empty() {
+        println!("{} variables resolved", report.usages.len());
+        exit(0);
+    } else {
+        println!("{} unresolved variables", unresolved.len());
+        exit(1);
+    }
+}
+
 fn run_test_group(
-    client: Client,
+    clients: HttpClients,
     workspace_data: WorkspaceData,
     test_group_run_result: Arc<RwLock<TestGroupRunResults>>,
-    collection_name: String,
     collection_path: String,
     parent_testcase: Option<Testcase>,
     folder: Rc<RefCell<CollectionFolder>>,
+    script_limits: ScriptLimits,
 ) {
-    let envs =
-        workspace_data.get_build_envs(workspace_data.get_collection(Some(collection_name.clone())));
-    let script_tree = workspace_data.get_script_tree(collection_path.clone());
+    let envs = workspace_data.get_path_envs(Some(collection_path.clone()));
+    let mut script_tree = workspace_data.get_script_tree(collection_path.clone());
+    script_tree.script_limits = script_limits;
     let folder_only_read = CollectionFolderOnlyRead::from(folder.clone());
     let run_request_infos = runner::Runner::get_test_group_jobs(
         envs.clone(),
@@ -76,7 +116,11 @@ fn run_test_group(
         parent_testcase,
         folder_only_read.clone(),
     );
-    runner::Runner::run_test_group_jobs(client,run_request_infos,test_group_run_result.clone(),true);
+    runner::Runner::run_test_group_jobs(clients,run_request_infos,test_group_run_result.clone(),true);
+    let env_changes = test_group_run_result.write().unwrap().take_env_changes();
+    for (collection_path, change) in env_changes {
+        workspace_data.apply_env_changes(collection_path, vec![change]);
+    }
     let result_tree = ResultTreeFolder::create(
         folder.clone(),
         vec![],